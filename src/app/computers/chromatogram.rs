@@ -0,0 +1,66 @@
+use super::table::{base_peak, error_frame, is_library, Key};
+use egui::util::cache::{ComputerMut, FrameCache};
+use polars::prelude::*;
+use tracing::trace;
+
+/// Chromatogram computed
+pub(crate) type Computed = FrameCache<DataFrame, Computer>;
+
+/// Chromatogram computer
+#[derive(Default)]
+pub(crate) struct Computer;
+
+impl ComputerMut<Key<'_>, DataFrame> for Computer {
+    /// Total signal (`Signal`) and base peak (`BasePeak.MassToCharge`,
+    /// `BasePeak.Signal`) of each scan (`RetentionTime`).
+    fn compute(&mut self, key: Key<'_>) -> DataFrame {
+        if is_library(key.data_frame) {
+            return DataFrame::empty();
+        }
+        let mut lazy_frame = key.data_frame.clone().lazy();
+        if key.settings.filter_null {
+            lazy_frame = lazy_frame.filter(col("MassSpectrum").list().len().neq(lit(0)));
+        }
+        let [base_peak_mass_to_charge, base_peak_signal] = base_peak();
+        let data_frame = lazy_frame
+            .select([
+                col("RetentionTime"),
+                col("MassSpectrum")
+                    .list()
+                    .eval(
+                        col("")
+                            .struct_()
+                            .field_by_name("Signal")
+                            .cast(DataType::Float64),
+                        true,
+                    )
+                    .list()
+                    .sum()
+                    .alias("Signal"),
+                base_peak_mass_to_charge,
+                base_peak_signal.cast(DataType::Float64),
+            ])
+            .sort_by_exprs([col("RetentionTime")], Default::default())
+            .collect();
+        trace!(?data_frame);
+        data_frame.unwrap_or_else(error_frame)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::computers::computed;
+
+    #[test]
+    fn error() -> PolarsResult<()> {
+        let data_frame = df! { "RetentionTime" => [1000] }?;
+        let error = computed(Computer.compute(Key {
+            data_frame: &data_frame,
+            settings: &Default::default(),
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("MassSpectrum"), "{error}");
+        Ok(())
+    }
+}
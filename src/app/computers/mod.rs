@@ -1,3 +1,9 @@
-pub(crate) use self::table::{Computed as TableComputed, Key as TableKey};
+pub(crate) use self::{
+    chromatogram::Computed as ChromatogramComputed,
+    peaks::{Computed as PeaksComputed, Key as PeaksKey},
+    table::{computed, is_library, Computed as TableComputed, Key as TableKey},
+};
 
+pub(crate) mod chromatogram;
+pub(crate) mod peaks;
 pub(crate) mod table;
@@ -0,0 +1,325 @@
+use crate::app::panes::peaks::{Algorithm, Detection};
+use egui::util::cache::{ComputerMut, FrameCache};
+use std::{
+    f64::consts::SQRT_2,
+    hash::{Hash, Hasher},
+    iter::successors,
+};
+
+/// Largest wavelet scale relative to the smallest (bounds the transform cost)
+const MAX_SCALE: f64 = 16.0;
+
+/// Peaks computed
+pub(crate) type Computed = FrameCache<Vec<Peak>, Computer>;
+
+/// Peaks computer
+#[derive(Default)]
+pub(crate) struct Computer;
+
+impl ComputerMut<Key<'_>, Vec<Peak>> for Computer {
+    fn compute(&mut self, key: Key<'_>) -> Vec<Peak> {
+        detect(key.points, key.settings)
+    }
+}
+
+/// Peaks key
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Key<'a> {
+    /// Trace (retention time, signal), sorted by retention time
+    pub(crate) points: &'a [[f64; 2]],
+    pub(crate) settings: &'a Detection,
+}
+
+impl Hash for Key<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for [x, y] in self.points {
+            x.to_bits().hash(state);
+            y.to_bits().hash(state);
+        }
+        self.settings.hash(state);
+    }
+}
+
+/// Chromatographic peak (points of the trace)
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Peak {
+    pub(crate) apex: [f64; 2],
+    pub(crate) start: [f64; 2],
+    pub(crate) end: [f64; 2],
+}
+
+impl Peak {
+    /// Apex signal above the higher boundary
+    pub(crate) fn prominence(&self) -> f64 {
+        self.apex[1] - self.start[1].max(self.end[1])
+    }
+}
+
+/// Peaks of the trace that pass the detection thresholds
+pub(crate) fn detect(points: &[[f64; 2]], settings: &Detection) -> Vec<Peak> {
+    let signal = points.iter().map(|&[_, y]| y).collect::<Vec<_>>();
+    let mut candidates = match settings.algorithm {
+        Algorithm::Derivative => derivative(&signal, settings.min_width),
+        Algorithm::Wavelet => wavelet(&signal, settings.min_width),
+    };
+    candidates.sort_unstable_by_key(|&[_, apex, _]| apex);
+    candidates.dedup_by_key(|&mut [_, apex, _]| apex);
+    let noise = noise(&signal);
+    candidates
+        .into_iter()
+        .filter_map(|[start, apex, end]| {
+            let peak = Peak {
+                apex: points[apex],
+                start: points[start],
+                end: points[end],
+            };
+            let prominence = peak.prominence();
+            (end - start + 1 >= settings.min_width
+                && peak.apex[1] >= settings.min_height
+                && prominence >= settings.min_prominence
+                && prominence >= settings.signal_to_noise * noise)
+                .then_some(peak)
+        })
+        .collect()
+}
+
+/// Derivative detection: apexes where the first derivative of the smoothed
+/// signal changes sign from positive to negative, boundaries at the nearest
+/// minima of the smoothed signal.
+fn derivative(signal: &[f64], min_width: usize) -> Vec<[usize; 3]> {
+    let smoothed = smooth(signal, min_width / 2);
+    let mut peaks = Vec::new();
+    for index in 1..smoothed.len().saturating_sub(1) {
+        if smoothed[index - 1] < smoothed[index] && smoothed[index] >= smoothed[index + 1] {
+            let mut start = index;
+            while start > 0 && smoothed[start - 1] < smoothed[start] {
+                start -= 1;
+            }
+            let mut end = index;
+            while end + 1 < smoothed.len() && smoothed[end + 1] < smoothed[end] {
+                end += 1;
+            }
+            peaks.push([start, apex(signal, start, end), end]);
+        }
+    }
+    peaks
+}
+
+/// Continuous wavelet transform detection: apexes at the maxima of the
+/// largest Ricker wavelet response over the scales, boundaries where the
+/// response at the apex scale changes sign.
+fn wavelet(signal: &[f64], min_width: usize) -> Vec<[usize; 3]> {
+    let length = signal.len();
+    let min_scale = (min_width as f64 / 4.0).max(1.0);
+    let max_scale = (length as f64 / 8.0)
+        .min(min_scale * MAX_SCALE)
+        .max(min_scale);
+    let coefficients = successors(Some(min_scale), |scale| Some(scale * SQRT_2))
+        .take_while(|&scale| scale <= max_scale)
+        .map(|scale| ricker(signal, scale))
+        .collect::<Vec<_>>();
+    // Largest response and its scale
+    let response = (0..length)
+        .map(|index| {
+            coefficients
+                .iter()
+                .enumerate()
+                .map(|(scale, coefficients)| (coefficients[index], scale))
+                .max_by(|left, right| left.0.total_cmp(&right.0))
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    let mut peaks = Vec::new();
+    for index in 1..length.saturating_sub(1) {
+        let (value, scale) = response[index];
+        if value > 0.0 && response[index - 1].0 < value && value >= response[index + 1].0 {
+            let coefficients = &coefficients[scale];
+            let mut start = index;
+            while start > 0 && coefficients[start - 1] > 0.0 {
+                start -= 1;
+            }
+            let mut end = index;
+            while end + 1 < length && coefficients[end + 1] > 0.0 {
+                end += 1;
+            }
+            peaks.push([start, apex(signal, start, end), end]);
+        }
+    }
+    peaks
+}
+
+/// Ricker (Mexican hat) wavelet transform at the scale
+fn ricker(signal: &[f64], scale: f64) -> Vec<f64> {
+    let radius = (5.0 * scale).ceil() as usize;
+    let kernel = (0..=2 * radius)
+        .map(|index| {
+            let t = (index as f64 - radius as f64) / scale;
+            (1.0 - t * t) * (-t * t / 2.0).exp() / scale.sqrt()
+        })
+        .collect::<Vec<_>>();
+    (0..signal.len())
+        .map(|index| {
+            let first = index.saturating_sub(radius);
+            let last = (index + radius).min(signal.len() - 1);
+            (first..=last)
+                .map(|position| signal[position] * kernel[position + radius - index])
+                .sum()
+        })
+        .collect()
+}
+
+/// Centered moving average
+fn smooth(signal: &[f64], radius: usize) -> Vec<f64> {
+    let mut sums = vec![0.0; signal.len() + 1];
+    for (index, value) in signal.iter().enumerate() {
+        sums[index + 1] = sums[index] + value;
+    }
+    (0..signal.len())
+        .map(|index| {
+            let first = index.saturating_sub(radius);
+            let last = (index + radius).min(signal.len() - 1);
+            (sums[last + 1] - sums[first]) / (last - first + 1) as f64
+        })
+        .collect()
+}
+
+/// Index of the highest signal within the boundaries
+fn apex(signal: &[f64], start: usize, end: usize) -> usize {
+    (start..=end)
+        .max_by(|&left, &right| signal[left].total_cmp(&signal[right]))
+        .unwrap_or(start)
+}
+
+/// Noise level: median absolute deviation of the first differences (robust
+/// to peaks), scaled to the standard deviation of the signal.
+fn noise(signal: &[f64]) -> f64 {
+    let mut differences = signal
+        .windows(2)
+        .map(|values| values[1] - values[0])
+        .collect::<Vec<_>>();
+    let center = median(&mut differences);
+    let mut deviations = differences
+        .iter()
+        .map(|difference| (difference - center).abs())
+        .collect::<Vec<_>>();
+    median(&mut deviations) * 1.4826 / SQRT_2
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_unstable_by(f64::total_cmp);
+    values[values.len() / 2]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::iter::zip;
+
+    /// Gaussian peaks `(apex index, height)` of the same width on a baseline
+    fn trace(peaks: &[(usize, f64)]) -> Vec<[f64; 2]> {
+        const SIGMA: f64 = 8.0;
+        (0..300)
+            .map(|index| {
+                let signal = peaks
+                    .iter()
+                    .map(|&(apex, height)| {
+                        let t = (index as f64 - apex as f64) / SIGMA;
+                        height * (-t * t / 2.0).exp()
+                    })
+                    .sum::<f64>();
+                [index as f64 / 10.0, 10.0 + signal]
+            })
+            .collect()
+    }
+
+    fn settings(algorithm: Algorithm) -> Detection {
+        Detection {
+            enabled: true,
+            algorithm,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn derivative() {
+        let points = trace(&[(100, 1000.0), (200, 400.0)]);
+        let peaks = detect(&points, &settings(Algorithm::Derivative));
+        assert_eq!(peaks.len(), 2);
+        assert_eq!(peaks[0].apex, points[100]);
+        // Boundaries at the baseline and at the shared valley
+        assert_eq!(peaks[0].start[1].round(), 10.0);
+        assert!(peaks[0].start[0] <= 7.6, "{:?}", peaks[0]);
+        assert_eq!(peaks[0].end, peaks[1].start);
+        assert!((14.0..=17.0).contains(&peaks[0].end[0]), "{:?}", peaks[0]);
+        assert_eq!(peaks[1].apex, points[200]);
+        assert_eq!(peaks[1].end[1].round(), 10.0);
+        assert!(peaks[1].end[0] >= 22.4, "{:?}", peaks[1]);
+    }
+
+    #[test]
+    fn wavelet() {
+        let points = trace(&[(100, 1000.0), (200, 400.0)]);
+        let peaks = detect(&points, &settings(Algorithm::Wavelet));
+        assert_eq!(peaks.len(), 2);
+        assert_eq!(peaks[0].apex, points[100]);
+        assert_eq!(peaks[1].apex, points[200]);
+        for (peak, apex) in zip(&peaks, [100, 200]) {
+            let [start, end] = [peak.start[0], peak.end[0]].map(|x| (x * 10.0).round() as usize);
+            assert!(start < apex && apex - start <= 40, "{peak:?}");
+            assert!(end > apex && end - apex <= 40, "{peak:?}");
+        }
+    }
+
+    #[test]
+    fn boundaries() {
+        // Valley between two equal peaks
+        let points = trace(&[(100, 1000.0), (160, 1000.0)]);
+        let peaks = detect(&points, &settings(Algorithm::Derivative));
+        assert_eq!(peaks.len(), 2);
+        assert_eq!(peaks[0].end, points[130]);
+        assert_eq!(peaks[1].start, points[130]);
+    }
+
+    #[test]
+    fn noise() {
+        // Median absolute deviation ignores the peak
+        let points = trace(&[]);
+        let mut signal = points.iter().map(|&[_, y]| y).collect::<Vec<_>>();
+        signal[150] = 1000.0;
+        assert_eq!(super::noise(&signal), 0.0);
+        // Gaussian noise (Box-Muller transform of a linear congruential
+        // generator) of standard deviation 2
+        let mut state = 1_u64;
+        let mut uniform = || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((state >> 11) as f64 + 0.5) / (1_u64 << 53) as f64
+        };
+        let signal = (0..10000)
+            .map(|_| {
+                let (u, v) = (uniform(), uniform());
+                2.0 * (-2.0 * u.ln()).sqrt() * (std::f64::consts::TAU * v).cos()
+            })
+            .collect::<Vec<_>>();
+        let noise = super::noise(&signal);
+        assert!((1.9..=2.1).contains(&noise), "{noise}");
+    }
+
+    #[test]
+    fn thresholds() {
+        let points = trace(&[(100, 1000.0), (200, 400.0)]);
+        let settings = Detection {
+            min_height: 500.0,
+            ..settings(Algorithm::Derivative)
+        };
+        let peaks = detect(&points, &settings);
+        assert_eq!(peaks.len(), 1);
+        assert_eq!(peaks[0].apex, points[100]);
+        assert!(detect(&[], &settings).is_empty());
+        assert!(detect(&points[..1], &settings).is_empty());
+    }
+}
@@ -1,7 +1,10 @@
-use crate::app::panes::settings::{Settings, Sort};
+use crate::app::panes::settings::{Centering, MassToCharge, Settings, Sort, Tolerance};
 use egui::util::cache::{ComputerMut, FrameCache};
 use polars::{frame::DataFrame, prelude::*};
-use std::hash::{Hash, Hasher};
+use std::{
+    hash::{Hash, Hasher},
+    iter::zip,
+};
 use tracing::{error, trace, warn};
 
 /// Filter computed
@@ -11,156 +14,320 @@ pub(crate) type Computed = FrameCache<DataFrame, Computer>;
 #[derive(Default)]
 pub(crate) struct Computer;
 
+/// Error message column of an error data frame
+const ERROR: &str = "Error";
+
 // fn signal() -> Expr {
 //     col("").struct_().field_by_name("Signal")
 // }
 
+/// Mass to charge and signal of the most intense peak of each mass spectrum
+pub(crate) fn base_peak() -> [Expr; 2] {
+    let signal = || col("").struct_().field_by_name("Signal");
+    [
+        col("MassSpectrum")
+            .list()
+            .eval(
+                col("")
+                    .struct_()
+                    .field_by_name("MassToCharge")
+                    .filter(signal().eq(signal().max()))
+                    .first(),
+                true,
+            )
+            .list()
+            .first()
+            .alias("BasePeak.MassToCharge"),
+        col("MassSpectrum")
+            .list()
+            .eval(signal(), true)
+            .list()
+            .max()
+            .alias("BasePeak.Signal"),
+    ]
+}
+
+/// Mass spectrum count, mass to charge and signal statistics, base peak
+fn mass_spectrum() -> [Expr; 8] {
+    let [base_peak_mass_to_charge, base_peak_signal] = base_peak();
+    [
+        col("MassSpectrum").list().len().name().suffix(".Count"),
+        col("MassSpectrum")
+            .list()
+            .eval(col("").struct_().field_by_name("MassToCharge"), true)
+            .list()
+            .min()
+            .alias("MassToCharge.Min"),
+        col("MassSpectrum")
+            .list()
+            .eval(col("").struct_().field_by_name("MassToCharge"), true)
+            .list()
+            .max()
+            .alias("MassToCharge.Max"),
+        col("MassSpectrum")
+            .list()
+            .eval(col("").struct_().field_by_name("Signal"), true)
+            .list()
+            .min()
+            .alias("Signal.Min"),
+        col("MassSpectrum")
+            .list()
+            .eval(col("").struct_().field_by_name("Signal"), true)
+            .list()
+            .max()
+            .alias("Signal.Max"),
+        col("MassSpectrum")
+            .list()
+            .eval(col("").struct_().field_by_name("Signal"), true)
+            .list()
+            .sum()
+            .alias("Signal.Sum"),
+        base_peak_mass_to_charge,
+        base_peak_signal,
+    ]
+}
+
+/// Computed data frame, or the error stored in it by the computer
+pub(crate) fn computed(data_frame: DataFrame) -> PolarsResult<DataFrame> {
+    match data_frame.column(ERROR) {
+        Ok(error) => polars_bail!(ComputeError: "{}", error.str()?.get(0).unwrap_or_default()),
+        Err(_) => Ok(data_frame),
+    }
+}
+
+/// Error data frame (cached values can't be results)
+pub(crate) fn error_frame(error: PolarsError) -> DataFrame {
+    error!(%error);
+    df! { ERROR => [error.to_string()] }.unwrap_or_default()
+}
+
+/// Spectral library (no `RetentionTime` column)
+pub(crate) fn is_library(data_frame: &DataFrame) -> bool {
+    data_frame.column("RetentionTime").is_err()
+}
+
 impl ComputerMut<Key<'_>, DataFrame> for Computer {
     fn compute(&mut self, key: Key<'_>) -> DataFrame {
-        let mut data_frame = key.data_frame.clone();
-        error!(?data_frame);
-        // {
-        //     let data_frame = data_frame
-        //         .clone()
-        //         .lazy()
-        //         .select([
-        //             col("RetentionTime"),
-        //             col("Masspectrum").alias("MassSpectrum"),
-        //         ])
-        //         // .explode(["Masspectrum"])
-        //         // .unnest(["Masspectrum"])
-        //         //     .sort(["MassToCharge"], Default::default())
-        //         //     .group_by([col("RetentionTime")])
-        //         //     .agg([as_struct(vec![
-        //         //         col("MassToCharge").drop_nulls(),
-        //         //         col("Signal").drop_nulls(),
-        //         //     ])
-        //         //     .alias("MassSpectrum")])
-        //         .collect()
-        //         .unwrap();
-        //     let contents = bincode::serialize(&data_frame).unwrap();
-        //     std::fs::write("df.msv.bin", &contents).unwrap();
-        //     // // let contents = ron::ser::to_string_pretty(&data_frame, Default::default()).unwrap();
-        //     // // std::fs::write("df.msv.ron", &contents).unwrap();
-        //     error!(?data_frame);
-        // }
-        let mut lazy_frame = data_frame.lazy();
-        if key.settings.filter_null {
-            lazy_frame = lazy_frame.filter(col("MassSpectrum").list().len().neq(lit(0)));
+        compute(key).unwrap_or_else(error_frame)
+    }
+}
+
+fn compute(key: Key<'_>) -> PolarsResult<DataFrame> {
+    let mut data_frame = key.data_frame.clone();
+    error!(?data_frame);
+    // {
+    //     let data_frame = data_frame
+    //         .clone()
+    //         .lazy()
+    //         .select([
+    //             col("RetentionTime"),
+    //             col("Masspectrum").alias("MassSpectrum"),
+    //         ])
+    //         // .explode(["Masspectrum"])
+    //         // .unnest(["Masspectrum"])
+    //         //     .sort(["MassToCharge"], Default::default())
+    //         //     .group_by([col("RetentionTime")])
+    //         //     .agg([as_struct(vec![
+    //         //         col("MassToCharge").drop_nulls(),
+    //         //         col("Signal").drop_nulls(),
+    //         //     ])
+    //         //     .alias("MassSpectrum")])
+    //         .collect()
+    //         .unwrap();
+    //     let contents = bincode::serialize(&data_frame).unwrap();
+    //     std::fs::write("df.msv.bin", &contents).unwrap();
+    //     // // let contents = ron::ser::to_string_pretty(&data_frame, Default::default()).unwrap();
+    //     // // std::fs::write("df.msv.ron", &contents).unwrap();
+    //     error!(?data_frame);
+    // }
+    let mut lazy_frame = data_frame.lazy();
+    if key.settings.filter_null {
+        lazy_frame = lazy_frame.filter(col("MassSpectrum").list().len().neq(lit(0)));
+    }
+    if is_library(key.data_frame) {
+        data_frame = lazy_frame.with_columns(mass_spectrum()).collect()?;
+        trace!(?data_frame);
+        return Ok(data_frame);
+    }
+    if key.settings.normalize {
+        lazy_frame = lazy_frame
+            .explode(["MassSpectrum"])
+            .unnest(["MassSpectrum"])
+            .with_column(col("Signal").cast(DataType::Float32) / max("Signal"))
+            .group_by([col("RetentionTime")])
+            .agg([as_struct(vec![col("MassToCharge"), col("Signal")]).alias("MassSpectrum")]);
+    }
+    match key.settings.sort {
+        Sort::RetentionTime if key.settings.explode => {
+            lazy_frame = lazy_frame
+                .explode(["MassSpectrum"])
+                .unnest(["MassSpectrum"])
+                .sort_by_exprs([col("RetentionTime")], Default::default());
+        }
+        Sort::RetentionTime if key.settings.base_peak => {
+            lazy_frame = lazy_frame.with_columns(mass_spectrum()).sort_by_exprs(
+                [col("BasePeak.MassToCharge"), col("RetentionTime")],
+                Default::default(),
+            );
+        }
+        Sort::RetentionTime => {
+            lazy_frame = lazy_frame
+                .with_columns(mass_spectrum())
+                .sort_by_exprs([col("RetentionTime")], Default::default());
         }
-        if key.settings.normalize {
+        Sort::MassToCharge if key.settings.explode => {
             lazy_frame = lazy_frame
                 .explode(["MassSpectrum"])
                 .unnest(["MassSpectrum"])
-                .with_column(col("Signal").cast(DataType::Float32) / max("Signal"))
-                .group_by([col("RetentionTime")])
-                .agg([as_struct(vec![col("MassToCharge"), col("Signal")]).alias("MassSpectrum")]);
+                .sort_by_exprs([col("MassToCharge")], Default::default());
         }
-        match key.settings.sort {
-            Sort::RetentionTime if key.settings.explode => {
-                lazy_frame = lazy_frame
-                    .explode(["MassSpectrum"])
-                    .unnest(["MassSpectrum"])
-                    .sort_by_exprs([col("RetentionTime")], Default::default());
-            }
-            Sort::RetentionTime => {
-                lazy_frame = lazy_frame
-                    .with_columns([
-                        col("MassSpectrum").list().len().name().suffix(".Count"),
-                        col("MassSpectrum")
-                            .list()
-                            .eval(col("").struct_().field_by_name("MassToCharge"), true)
-                            .list()
-                            .min()
-                            .alias("MassToCharge.Min"),
-                        col("MassSpectrum")
-                            .list()
-                            .eval(col("").struct_().field_by_name("MassToCharge"), true)
-                            .list()
-                            .max()
-                            .alias("MassToCharge.Max"),
-                        col("MassSpectrum")
-                            .list()
-                            .eval(col("").struct_().field_by_name("Signal"), true)
-                            .list()
-                            .min()
-                            .alias("Signal.Min"),
-                        col("MassSpectrum")
-                            .list()
-                            .eval(col("").struct_().field_by_name("Signal"), true)
-                            .list()
-                            .max()
-                            .alias("Signal.Max"),
-                        col("MassSpectrum")
-                            .list()
-                            .eval(col("").struct_().field_by_name("Signal"), true)
-                            .list()
-                            .sum()
-                            .alias("Signal.Sum"),
-                    ])
-                    .sort_by_exprs([col("RetentionTime")], Default::default());
-            }
-            Sort::MassToCharge if key.settings.explode => {
-                lazy_frame = lazy_frame
-                    .explode(["MassSpectrum"])
-                    .unnest(["MassSpectrum"])
-                    .sort_by_exprs([col("MassToCharge")], Default::default());
-            }
-            Sort::MassToCharge => {
-                trace!(lazy_data_frame =? lazy_frame.clone().collect());
-                lazy_frame = lazy_frame
-                    .explode(["MassSpectrum"])
-                    .unnest(["MassSpectrum"])
-                    .sort_by_exprs([col("RetentionTime")], Default::default())
-                    .group_by([col("MassToCharge").round(0)])
-                    .agg([as_struct(vec![
-                        col("RetentionTime").drop_nulls(),
-                        col("Signal").drop_nulls(),
-                    ])
-                    .alias("ExtractedIonChromatogram")])
-                    .sort_by_exprs([col("MassToCharge")], Default::default())
-                    .with_columns([
-                        col("ExtractedIonChromatogram")
-                            .list()
-                            .len()
-                            .name()
-                            .suffix(".Count"),
-                        col("ExtractedIonChromatogram")
-                            .list()
-                            .eval(col("").struct_().field_by_name("RetentionTime"), true)
-                            .list()
-                            .min()
-                            .alias("RetentionTime.Min"),
-                        col("ExtractedIonChromatogram")
-                            .list()
-                            .eval(col("").struct_().field_by_name("RetentionTime"), true)
-                            .list()
-                            .max()
-                            .alias("RetentionTime.Max"),
-                        col("ExtractedIonChromatogram")
-                            .list()
-                            .eval(col("").struct_().field_by_name("Signal"), true)
-                            .list()
-                            .min()
-                            .alias("Signal.Min"),
-                        col("ExtractedIonChromatogram")
-                            .list()
-                            .eval(col("").struct_().field_by_name("Signal"), true)
-                            .list()
-                            .max()
-                            .alias("Signal.Max"),
-                        col("ExtractedIonChromatogram")
-                            .list()
-                            .eval(col("").struct_().field_by_name("Signal"), true)
-                            .list()
-                            .sum()
-                            .alias("Signal.Sum"),
-                    ]);
+        Sort::MassToCharge => {
+            trace!(lazy_data_frame =? lazy_frame.clone().collect());
+            lazy_frame = extracted_ion_chromatograms(lazy_frame, &key.settings.mass_to_charge)?
+                .with_columns([
+                    col("ExtractedIonChromatogram")
+                        .list()
+                        .len()
+                        .name()
+                        .suffix(".Count"),
+                    col("ExtractedIonChromatogram")
+                        .list()
+                        .eval(col("").struct_().field_by_name("RetentionTime"), true)
+                        .list()
+                        .min()
+                        .alias("RetentionTime.Min"),
+                    col("ExtractedIonChromatogram")
+                        .list()
+                        .eval(col("").struct_().field_by_name("RetentionTime"), true)
+                        .list()
+                        .max()
+                        .alias("RetentionTime.Max"),
+                    col("ExtractedIonChromatogram")
+                        .list()
+                        .eval(col("").struct_().field_by_name("Signal"), true)
+                        .list()
+                        .min()
+                        .alias("Signal.Min"),
+                    col("ExtractedIonChromatogram")
+                        .list()
+                        .eval(col("").struct_().field_by_name("Signal"), true)
+                        .list()
+                        .max()
+                        .alias("Signal.Max"),
+                    col("ExtractedIonChromatogram")
+                        .list()
+                        .eval(col("").struct_().field_by_name("Signal"), true)
+                        .list()
+                        .sum()
+                        .alias("Signal.Sum"),
+                ]);
+        }
+    };
+    data_frame = lazy_frame.collect()?;
+    trace!(?data_frame);
+    Ok(data_frame)
+}
+
+/// Extracted ion chromatogram of each mass to charge window
+/// (`MassToCharge` is the window center), the signal within a window is
+/// summed per scan.
+fn extracted_ion_chromatograms(
+    lazy_frame: LazyFrame,
+    settings: &MassToCharge,
+) -> PolarsResult<LazyFrame> {
+    let data_frame = lazy_frame
+        .explode(["MassSpectrum"])
+        .unnest(["MassSpectrum"])
+        .filter(
+            col("RetentionTime")
+                .is_not_null()
+                .and(col("MassToCharge").is_not_null()),
+        )
+        .sort_by_exprs([col("MassToCharge")], Default::default())
+        .collect()?;
+    let mass_to_charge = data_frame["MassToCharge"]
+        .f32()?
+        .into_no_null_iter()
+        .collect::<Vec<_>>();
+    let mut centers = match settings.centering {
+        Centering::Nominal => mass_to_charge.iter().map(|value| value.round()).collect(),
+        Centering::List => settings.list.clone(),
+        Centering::Centroid => {
+            let signal = data_frame["Signal"].cast(&DataType::Float64)?;
+            let signal = signal
+                .f64()?
+                .into_iter()
+                .map(Option::unwrap_or_default)
+                .collect::<Vec<_>>();
+            centroids(&mass_to_charge, &signal, settings.tolerance)
+        }
+    };
+    centers.sort_by(f32::total_cmp);
+    centers.dedup();
+    // Rows of each window (sorted by mass to charge)
+    let mut indices = Vec::new();
+    let mut windows = Vec::new();
+    for center in centers {
+        let tolerance = settings.tolerance.dalton(center);
+        let start = mass_to_charge.partition_point(|&value| value < center - tolerance);
+        let end = mass_to_charge.partition_point(|&value| value < center + tolerance);
+        indices.extend(start as IdxSize..end as IdxSize);
+        windows.resize(windows.len() + end - start, center);
+    }
+    let mut data_frame = data_frame.take(&IdxCa::from_vec("", indices))?;
+    data_frame.with_column(Series::new("MassToCharge", windows))?;
+    Ok(data_frame
+        .lazy()
+        .group_by([col("MassToCharge"), col("RetentionTime")])
+        .agg([col("Signal").sum()])
+        .sort_by_exprs([col("RetentionTime")], Default::default())
+        .group_by([col("MassToCharge")])
+        .agg([
+            as_struct(vec![col("RetentionTime"), col("Signal")]).alias("ExtractedIonChromatogram")
+        ])
+        .sort_by_exprs([col("MassToCharge")], Default::default()))
+}
+
+/// Signal weighted centroids of the peaks (sorted by mass to charge), a
+/// point within the tolerance of the current centroid belongs to its peak.
+fn centroids(mass_to_charge: &[f32], signal: &[f64], tolerance: Tolerance) -> Vec<f32> {
+    let mut peaks = Vec::<Peak>::new();
+    for (&mass_to_charge, &signal) in zip(mass_to_charge, signal) {
+        let tolerance = tolerance.dalton(mass_to_charge) as f64;
+        let (mass_to_charge, signal) = (mass_to_charge as f64, signal.max(0.0));
+        match peaks.last_mut() {
+            Some(peak) if mass_to_charge - peak.centroid() < tolerance => {
+                peak.moment += mass_to_charge * signal;
+                peak.signal += signal;
+                peak.last = mass_to_charge;
             }
-        };
-        data_frame = lazy_frame.collect().unwrap();
-        trace!(?data_frame);
-        data_frame
+            _ => peaks.push(Peak {
+                moment: mass_to_charge * signal,
+                signal,
+                first: mass_to_charge,
+                last: mass_to_charge,
+            }),
+        }
+    }
+    peaks.iter().map(|peak| peak.centroid() as _).collect()
+}
+
+/// Peak being centroided
+struct Peak {
+    /// Signal weighted mass to charge sum
+    moment: f64,
+    signal: f64,
+    first: f64,
+    last: f64,
+}
+
+impl Peak {
+    fn centroid(&self) -> f64 {
+        if self.signal > 0.0 {
+            self.moment / self.signal
+        } else {
+            (self.first + self.last) / 2.0
+        }
     }
 }
 
@@ -173,7 +340,56 @@ pub struct Key<'a> {
 
 impl Hash for Key<'_> {
     fn hash<H: Hasher>(&self, state: &mut H) {
-        // self.context.state.index.hash(state);
+        // Column buffers are shared between clones, so their addresses identify
+        // the data frame
+        for series in self.data_frame.get_columns() {
+            (Arc::as_ptr(&series.0) as *const () as usize).hash(state);
+        }
         self.settings.hash(state);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::formats::{data_frame, Spectrum};
+
+    fn settings() -> Settings {
+        Settings {
+            sort: Sort::MassToCharge,
+            mass_to_charge: MassToCharge {
+                centering: Centering::List,
+                list: Vec::new(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn empty_list() -> PolarsResult<()> {
+        let data_frame = data_frame(&[Spectrum {
+            retention_time: 1000.0,
+            mass_to_charge: vec![73.0, 147.1],
+            signal: vec![100.0, 25.0],
+        }])?;
+        let computed = computed(Computer.compute(Key {
+            data_frame: &data_frame,
+            settings: &settings(),
+        }))?;
+        assert_eq!(computed.height(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn error() -> PolarsResult<()> {
+        let data_frame = df! { "RetentionTime" => [1000] }?;
+        let error = computed(Computer.compute(Key {
+            data_frame: &data_frame,
+            settings: &settings(),
+        }))
+        .unwrap_err();
+        assert!(error.to_string().contains("MassSpectrum"), "{error}");
+        Ok(())
+    }
+}
@@ -1,32 +1,75 @@
+use crate::app::formats::{compress, Format};
 use anyhow::Result;
 use polars::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::{
+    collections::BTreeMap,
     fmt::{self, Display, Formatter},
-    fs::write,
-    path::Path,
+    path::{Component, Path},
 };
 
+/// Extensions stripped from the dataset name besides the format ones
+const EXTENSIONS: [&str; 5] = ["d", "gz", "msv", "zip", "zst"];
+
+/// Dataset metadata (sample name, instrument, date, ...)
+pub(crate) type Metadata = BTreeMap<String, String>;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub(crate) struct Data {
     pub(crate) data_frame: DataFrame,
+    #[serde(default)]
+    pub(crate) metadata: Metadata,
 }
 
 impl Data {
-    pub(crate) fn save(&self, path: impl AsRef<Path>, format: Format) -> Result<()> {
-        let data_frame = self.data_frame.select(["RetentionTime", "Masspectrum"])?;
-        match format {
-            Format::Bin => {
-                let contents = bincode::serialize(&data_frame)?;
-                write(path, contents)?;
-            }
-            Format::Ron => {
-                let contents = ron::ser::to_string_pretty(&data_frame, Default::default())?;
-                write(path, contents)?;
-            }
+    pub(crate) fn new(data_frame: DataFrame) -> Self {
+        Self {
+            data_frame,
+            metadata: Metadata::new(),
         }
-        Ok(())
     }
+
+    /// Dataset name, see [`name`].
+    pub(crate) fn name(&self) -> String {
+        name(&self.metadata)
+    }
+
+    /// Writes the data in the format and compresses it.
+    pub(crate) fn to_bytes(&self, format: Format, compression: Compression) -> Result<Vec<u8>> {
+        compress(format.write(self)?, compression)
+    }
+}
+
+/// Dataset name: the loaded file name without extensions (`RUN01` for
+/// `RUN01.D/DATA.MS` or `run.mzML.gz`), otherwise the sample name.
+pub(crate) fn name(metadata: &Metadata) -> String {
+    let name = metadata.get("FileName").and_then(|file_name| {
+        let path = Path::new(file_name);
+        let component = if path.file_name()?.eq_ignore_ascii_case("DATA.MS") {
+            path.components().rev().nth(1)
+        } else {
+            path.components().next_back()
+        };
+        let Some(Component::Normal(name)) = component else {
+            return None;
+        };
+        let mut name = Path::new(name);
+        while let Some(extension) = name.extension().and_then(|extension| extension.to_str()) {
+            let extension = extension.to_lowercase();
+            if !EXTENSIONS.contains(&&*extension)
+                && !Format::ALL
+                    .into_iter()
+                    .any(|format| format.extensions().contains(&&*extension))
+            {
+                break;
+            }
+            name = Path::new(name.file_stem()?);
+        }
+        Some(name.to_str()?.to_owned())
+    });
+    name.or_else(|| metadata.get("SampleName").cloned())
+        .filter(|name| !name.trim().is_empty())
+        .unwrap_or_else(|| "data".to_owned())
 }
 
 impl Display for Data {
@@ -48,13 +91,35 @@ impl Default for Data {
                     ]))),
                 ),
             ])),
+            metadata: Metadata::new(),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Default)]
-pub(crate) enum Format {
+/// Output compression
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) enum Compression {
     #[default]
-    Bin,
-    Ron,
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// File name suffix
+    pub(crate) const fn extension(self) -> &'static str {
+        match self {
+            Self::None => "",
+            Self::Gzip => ".gz",
+            Self::Zstd => ".zst",
+        }
+    }
+
+    pub(crate) const fn text(self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Gzip => "Gzip",
+            Self::Zstd => "Zstd",
+        }
+    }
 }
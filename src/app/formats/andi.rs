@@ -0,0 +1,150 @@
+use super::{
+    data_frame,
+    netcdf::{NetCdf, Value},
+    Spectrum,
+};
+use crate::app::data::Metadata;
+use anyhow::{ensure, Context, Result};
+use polars::frame::DataFrame;
+use tracing::info;
+use uom::si::{
+    f64::Time,
+    time::{millisecond, second},
+};
+
+// ASTM E2077 (ANDI-MS, netCDF AIA)
+
+/// Reads ANDI-MS into a data frame with global attributes as metadata.
+pub(crate) fn read(bytes: &[u8]) -> Result<(DataFrame, Metadata)> {
+    let net_cdf = NetCdf::new(bytes)?;
+    let scan_acquisition_time = net_cdf.variable("scan_acquisition_time")?;
+    let scan_index = net_cdf.variable("scan_index")?;
+    let point_count = net_cdf.variable("point_count")?;
+    let mass_values = scaled(&net_cdf, "mass_values")?;
+    let intensity_values = scaled(&net_cdf, "intensity_values")?;
+    ensure!(
+        scan_acquisition_time.len() == scan_index.len() && scan_index.len() == point_count.len(),
+        "scan variables have different lengths",
+    );
+    ensure!(
+        mass_values.len() == intensity_values.len(),
+        "mass and intensity values have different lengths",
+    );
+    let mut spectra = Vec::with_capacity(scan_index.len());
+    for ((time, index), count) in scan_acquisition_time
+        .into_iter()
+        .zip(scan_index)
+        .zip(point_count)
+    {
+        let range = index as usize..(index + count) as usize;
+        let mass_to_charge = mass_values
+            .get(range.clone())
+            .with_context(|| format!("scan points {range:?} are out of bounds"))?;
+        let signal = &intensity_values[range];
+        spectra.push(Spectrum {
+            retention_time: Time::new::<second>(time).get::<millisecond>(),
+            mass_to_charge: mass_to_charge.iter().map(|&value| value as _).collect(),
+            signal: signal.iter().map(|&value| value as _).collect(),
+        });
+    }
+    let metadata = net_cdf
+        .attributes
+        .iter()
+        .map(|attribute| (attribute.name.clone(), attribute.value.to_string()))
+        .collect();
+    info!(spectra = spectra.len(), "ANDI-MS");
+    Ok((data_frame(&spectra)?, metadata))
+}
+
+/// Variable values multiplied by its `scale_factor` attribute
+fn scaled(net_cdf: &NetCdf, name: &str) -> Result<Vec<f64>> {
+    let mut values = net_cdf.variable(name)?;
+    if let Some(scale_factor) = net_cdf
+        .variable_attribute(name, "scale_factor")
+        .and_then(Value::number)
+    {
+        for value in &mut values {
+            *value *= scale_factor;
+        }
+    }
+    Ok(values)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::formats::spectra;
+
+    /// CDF-1 file with two scans (16-bit masses with a scale factor, float
+    /// intensities) and sample, date and instrument attributes
+    const ANDI: &[u8] = &[
+        0x43, 0x44, 0x46, 0x01, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00,
+        0x02, 0x00, 0x00, 0x00, 0x0b, 0x73, 0x63, 0x61, 0x6e, 0x5f, 0x6e, 0x75, 0x6d, 0x62, 0x65,
+        0x72, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x0c, 0x70, 0x6f, 0x69, 0x6e, 0x74,
+        0x5f, 0x6e, 0x75, 0x6d, 0x62, 0x65, 0x72, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0c,
+        0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x0b, 0x73, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x5f,
+        0x6e, 0x61, 0x6d, 0x65, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x0b, 0x54, 0x65,
+        0x73, 0x74, 0x20, 0x73, 0x61, 0x6d, 0x70, 0x6c, 0x65, 0x00, 0x00, 0x00, 0x00, 0x1a, 0x65,
+        0x78, 0x70, 0x65, 0x72, 0x69, 0x6d, 0x65, 0x6e, 0x74, 0x5f, 0x64, 0x61, 0x74, 0x65, 0x5f,
+        0x74, 0x69, 0x6d, 0x65, 0x5f, 0x73, 0x74, 0x61, 0x6d, 0x70, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x02, 0x00, 0x00, 0x00, 0x13, 0x32, 0x30, 0x32, 0x34, 0x30, 0x31, 0x30, 0x31, 0x31, 0x32,
+        0x30, 0x30, 0x30, 0x30, 0x2b, 0x30, 0x30, 0x30, 0x30, 0x00, 0x00, 0x00, 0x00, 0x0f, 0x69,
+        0x6e, 0x73, 0x74, 0x72, 0x75, 0x6d, 0x65, 0x6e, 0x74, 0x5f, 0x6e, 0x61, 0x6d, 0x65, 0x00,
+        0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x04, 0x47, 0x43, 0x4d, 0x53, 0x00, 0x00, 0x00,
+        0x0b, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x15, 0x73, 0x63, 0x61, 0x6e, 0x5f, 0x61,
+        0x63, 0x71, 0x75, 0x69, 0x73, 0x69, 0x74, 0x69, 0x6f, 0x6e, 0x5f, 0x74, 0x69, 0x6d, 0x65,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x01,
+        0xd4, 0x00, 0x00, 0x00, 0x0a, 0x73, 0x63, 0x61, 0x6e, 0x5f, 0x69, 0x6e, 0x64, 0x65, 0x78,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x01, 0xe4,
+        0x00, 0x00, 0x00, 0x0b, 0x70, 0x6f, 0x69, 0x6e, 0x74, 0x5f, 0x63, 0x6f, 0x75, 0x6e, 0x74,
+        0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x01, 0xec, 0x00,
+        0x00, 0x00, 0x0b, 0x6d, 0x61, 0x73, 0x73, 0x5f, 0x76, 0x61, 0x6c, 0x75, 0x65, 0x73, 0x00,
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00,
+        0x01, 0x00, 0x00, 0x00, 0x0c, 0x73, 0x63, 0x61, 0x6c, 0x65, 0x5f, 0x66, 0x61, 0x63, 0x74,
+        0x6f, 0x72, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x01, 0x3f, 0xf0, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x01, 0xf4,
+        0x00, 0x00, 0x00, 0x10, 0x69, 0x6e, 0x74, 0x65, 0x6e, 0x73, 0x69, 0x74, 0x79, 0x5f, 0x76,
+        0x61, 0x6c, 0x75, 0x65, 0x73, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x04, 0x00,
+        0x00, 0x01, 0xf8, 0x40, 0x4e, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x4e, 0xc0, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+        0x03, 0x00, 0x00, 0x00, 0x02, 0x00, 0x32, 0x00, 0x00, 0x41, 0x20, 0x00, 0x00, 0x00, 0x49,
+        0x00, 0x00, 0x44, 0xfa, 0x00, 0x00, 0x00, 0x93, 0x00, 0x00, 0x43, 0xfa, 0x00, 0x00, 0x00,
+        0x33, 0x00, 0x00, 0x47, 0xc3, 0x50, 0x00, 0x00, 0x4a, 0x00, 0x00, 0x40, 0x40, 0x00, 0x00,
+    ];
+
+    #[test]
+    fn read() -> Result<()> {
+        let (data_frame, metadata) = super::read(ANDI)?;
+        assert_eq!(
+            spectra(&data_frame)?,
+            [
+                Spectrum {
+                    retention_time: 60000.0,
+                    mass_to_charge: vec![50.0, 73.0, 147.0],
+                    signal: vec![10.0, 2000.0, 500.0],
+                },
+                Spectrum {
+                    retention_time: 61500.0,
+                    mass_to_charge: vec![51.0, 74.0],
+                    signal: vec![100000.0, 3.0],
+                },
+            ],
+        );
+        assert_eq!(metadata["sample_name"], "Test sample");
+        assert_eq!(metadata["instrument_name"], "GCMS");
+        Ok(())
+    }
+
+    #[test]
+    fn out_of_bounds() {
+        // `mass_values` begins past the end of the file
+        let mut bytes = ANDI.to_vec();
+        bytes[0x1A0..0x1A4].copy_from_slice(&u32::MAX.to_be_bytes());
+        let error = super::read(&bytes).unwrap_err();
+        assert_eq!(error.to_string(), "variable mass_values is out of bounds");
+    }
+}
@@ -0,0 +1,158 @@
+use super::{data_frame, Spectrum};
+use crate::app::data::Metadata;
+use anyhow::{ensure, Context, Result};
+use polars::frame::DataFrame;
+use tracing::info;
+
+// https://github.com/evanyeyeye/rainbow (rainbow/agilent/chemstation.py)
+// https://github.com/bovee/aston (aston/tracefile/agilent_ms.py)
+
+/// Pascal string "2" (file version) followed by two zero bytes
+const MAGIC: [u8; 4] = [0x01, 0x32, 0x00, 0x00];
+const DATA_OFFSET: usize = 0x10A;
+const SCANS: usize = 0x142;
+/// Header pascal strings
+const METADATA: [(&str, usize); 5] = [
+    ("SampleName", 0x18),
+    ("Operator", 0x94),
+    ("AcquisitionDate", 0xB2),
+    ("Instrument", 0xD0),
+    ("AcquisitionMethod", 0xE4),
+];
+
+/// Reads an Agilent ChemStation `DATA.MS` file into a data frame with the
+/// header sample name, operator, date, instrument and method as metadata.
+pub(crate) fn read(bytes: &[u8]) -> Result<(DataFrame, Metadata)> {
+    ensure!(
+        bytes.starts_with(&MAGIC),
+        "not an Agilent ChemStation MS file",
+    );
+    let mut metadata = Metadata::new();
+    for (name, offset) in METADATA {
+        let value = string(bytes, offset)?;
+        if !value.is_empty() {
+            metadata.insert(name.to_owned(), value);
+        }
+    }
+    let count = u16(bytes, SCANS)? as usize;
+    // Offset is stored in 16-bit words, one based
+    let mut offset = (2 * u16(bytes, DATA_OFFSET)? as usize)
+        .checked_sub(2)
+        .context("invalid data offset")?;
+    let mut spectra = Vec::with_capacity(count);
+    for _ in 0..count {
+        // Record: length (words), time (ms), 6 bytes, pair count, 4 bytes,
+        // m/z-abundance pairs, trailer
+        let length = 2 * u16(bytes, offset)? as usize;
+        ensure!(length != 0, "invalid scan record length at {offset:#x}");
+        let retention_time = u32(bytes, offset + 2)?;
+        let pairs = u16(bytes, offset + 12)? as usize;
+        let mut peaks = (0..pairs)
+            .map(|index| {
+                let position = offset + 18 + 4 * index;
+                let mass_to_charge = u16(bytes, position)? as f32 / 20.0;
+                Ok((mass_to_charge, abundance(u16(bytes, position + 2)?)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        // Pairs are stored by descending m/z
+        peaks.sort_by(|left, right| left.0.total_cmp(&right.0));
+        spectra.push(Spectrum {
+            retention_time: retention_time as _,
+            mass_to_charge: peaks.iter().map(|peak| peak.0).collect(),
+            signal: peaks.iter().map(|peak| peak.1).collect(),
+        });
+        offset += length;
+    }
+    info!(spectra = spectra.len(), "ChemStation");
+    Ok((data_frame(&spectra)?, metadata))
+}
+
+/// Abundance: 14-bit mantissa with a 2-bit base 8 exponent
+fn abundance(value: u16) -> f32 {
+    (value & 0x3FFF) as f32 * 8f32.powi((value >> 14) as _)
+}
+
+/// Pascal string (length byte followed by the text)
+fn string(bytes: &[u8], offset: usize) -> Result<String> {
+    let length = *bytes.get(offset).context("unexpected end of header")? as usize;
+    let text = bytes
+        .get(offset + 1..offset + 1 + length)
+        .context("unexpected end of header")?;
+    Ok(String::from_utf8_lossy(text).trim().to_owned())
+}
+
+fn u16(bytes: &[u8], offset: usize) -> Result<u16> {
+    let bytes = bytes
+        .get(offset..offset + 2)
+        .with_context(|| format!("unexpected end of file at {offset:#x}"))?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn u32(bytes: &[u8], offset: usize) -> Result<u32> {
+    let bytes = bytes
+        .get(offset..offset + 4)
+        .with_context(|| format!("unexpected end of file at {offset:#x}"))?;
+    Ok(u32::from_be_bytes(bytes.try_into()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::formats::spectra;
+
+    /// Header with a sample name and method, two scan records (retention time,
+    /// pairs by descending m/z)
+    fn fixture() -> Vec<u8> {
+        let mut bytes = vec![0; 0x200];
+        bytes[..4].copy_from_slice(&MAGIC);
+        bytes[0x18] = 6;
+        bytes[0x19..0x1F].copy_from_slice(b"Sample");
+        bytes[0xE4] = 6;
+        bytes[0xE5..0xEB].copy_from_slice(b"M1.M  ");
+        bytes[SCANS..SCANS + 2].copy_from_slice(&2_u16.to_be_bytes());
+        let words = (bytes.len() / 2 + 1) as u16;
+        bytes[DATA_OFFSET..DATA_OFFSET + 2].copy_from_slice(&words.to_be_bytes());
+        // m/z × 20, abundance with a base 8 exponent in the top bits
+        let scans: [(u32, &[(u16, u16)]); 2] = [
+            (1500, &[(2002, 100), (1000, 1 << 14 | 3)]),
+            (3000, &[(800, 7)]),
+        ];
+        for (retention_time, pairs) in scans {
+            let mut record = vec![0; 18 + 4 * pairs.len() + 10];
+            let words = (record.len() / 2) as u16;
+            record[0..2].copy_from_slice(&words.to_be_bytes());
+            record[2..6].copy_from_slice(&retention_time.to_be_bytes());
+            record[12..14].copy_from_slice(&(pairs.len() as u16).to_be_bytes());
+            for (index, (mass_to_charge, abundance)) in pairs.iter().enumerate() {
+                let position = 18 + 4 * index;
+                record[position..position + 2].copy_from_slice(&mass_to_charge.to_be_bytes());
+                record[position + 2..position + 4].copy_from_slice(&abundance.to_be_bytes());
+            }
+            bytes.extend(record);
+        }
+        bytes
+    }
+
+    #[test]
+    fn read() -> Result<()> {
+        let (data_frame, metadata) = super::read(&fixture())?;
+        assert_eq!(
+            spectra(&data_frame)?,
+            [
+                Spectrum {
+                    retention_time: 1500.0,
+                    mass_to_charge: vec![50.0, 100.1],
+                    signal: vec![24.0, 100.0],
+                },
+                Spectrum {
+                    retention_time: 3000.0,
+                    mass_to_charge: vec![40.0],
+                    signal: vec![7.0],
+                },
+            ],
+        );
+        assert_eq!(metadata["SampleName"], "Sample");
+        assert_eq!(metadata["AcquisitionMethod"], "M1.M");
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+use crate::app::data::Compression;
+#[cfg(target_arch = "wasm32")]
+use anyhow::bail;
+use anyhow::{Context, Result};
+use flate2::{read::MultiGzDecoder, write::GzEncoder};
+use std::{
+    io::{Cursor, Read, Write},
+    path::Path,
+};
+use tracing::info;
+use zip::ZipArchive;
+
+const GZIP: [u8; 2] = [0x1F, 0x8B];
+const ZIP: [u8; 4] = [0x50, 0x4B, 0x03, 0x04];
+const ZSTD: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Decompresses gzip, zstd and zip content (detected by magic bytes).
+///
+/// Returns the content with its file name: the name without the compression
+/// extension (`run.mzML.gz` is `run.mzML`) or the zip entry name. Returns
+/// `None` for uncompressed content.
+pub(crate) fn decompress(bytes: &[u8], name: &str) -> Result<Option<(Vec<u8>, String)>> {
+    let stem = || {
+        Path::new(name)
+            .file_stem()
+            .map_or_else(String::new, |stem| stem.to_string_lossy().into_owned())
+    };
+    let mut decompressed = Vec::new();
+    if bytes.starts_with(&GZIP) {
+        MultiGzDecoder::new(bytes).read_to_end(&mut decompressed)?;
+        info!(name, "gzip");
+        Ok(Some((decompressed, stem())))
+    } else if bytes.starts_with(&ZSTD) {
+        #[cfg(not(target_arch = "wasm32"))]
+        zstd::stream::read::Decoder::new(bytes)?.read_to_end(&mut decompressed)?;
+        // Pure Rust decoder, the zstd crate binds the C library
+        #[cfg(target_arch = "wasm32")]
+        ruzstd::StreamingDecoder::new(bytes)?.read_to_end(&mut decompressed)?;
+        info!(name, "zstd");
+        Ok(Some((decompressed, stem())))
+    } else if bytes.starts_with(&ZIP) {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+        // Agilent `.D` directory data file first, otherwise the first file
+        let names = archive
+            .file_names()
+            .filter(|name| !name.ends_with('/') && !name.starts_with("__MACOSX/"))
+            .map(ToOwned::to_owned)
+            .collect::<Vec<_>>();
+        let entry = names
+            .iter()
+            .find(|name| {
+                Path::new(name)
+                    .file_name()
+                    .is_some_and(|name| name.eq_ignore_ascii_case("DATA.MS"))
+            })
+            .or(names.first())
+            .with_context(|| format!("{name} has no files"))?;
+        archive.by_name(entry)?.read_to_end(&mut decompressed)?;
+        info!(name, entry, files = names.len(), "zip");
+        Ok(Some((decompressed, entry.clone())))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Compresses content (zstd is native only).
+pub(crate) fn compress(bytes: Vec<u8>, compression: Compression) -> Result<Vec<u8>> {
+    Ok(match compression {
+        Compression::None => bytes,
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(Vec::new(), Default::default());
+            encoder.write_all(&bytes)?;
+            encoder.finish()?
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        Compression::Zstd => zstd::encode_all(&*bytes, 0)?,
+        #[cfg(target_arch = "wasm32")]
+        Compression::Zstd => bail!("zstd compression is not available on the web"),
+    })
+}
@@ -0,0 +1,126 @@
+use crate::app::panes::settings::TimeUnits;
+use anyhow::Result;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use uom::si::{
+    f64::Time,
+    time::{millisecond, minute, second},
+};
+
+/// CSV/TSV options
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(crate) struct Options {
+    pub(crate) delimiter: Delimiter,
+    pub(crate) decimal: Decimal,
+    pub(crate) header: bool,
+}
+
+/// Long format (row per peak) columns
+#[derive(Clone, Debug, Default, Deserialize, Hash, PartialEq, Serialize)]
+pub(crate) struct Columns {
+    pub(crate) retention_time: String,
+    pub(crate) mass_to_charge: String,
+    pub(crate) signal: String,
+    pub(crate) units: TimeUnits,
+}
+
+/// Writes the data frame with a header row.
+pub(crate) fn write(data_frame: &mut DataFrame, delimiter: Delimiter) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    CsvWriter::new(&mut bytes)
+        .include_header(true)
+        .with_separator(delimiter.byte())
+        .finish(data_frame)?;
+    Ok(bytes)
+}
+
+/// Reads the first rows as they are, to choose the columns.
+pub(crate) fn preview(bytes: &[u8], options: Options, rows: usize) -> Result<DataFrame> {
+    Ok(reader(bytes, options, Some(rows)).finish()?)
+}
+
+/// Reads long format rows and groups them by retention time into the
+/// `MassSpectrum` list.
+pub(crate) fn read(bytes: &[u8], options: Options, columns: &Columns) -> Result<DataFrame> {
+    let data_frame = reader(bytes, options, None).finish()?;
+    // Milliseconds per unit
+    let time = match columns.units {
+        TimeUnits::Millisecond => Time::new::<millisecond>(1.0),
+        TimeUnits::Second => Time::new::<second>(1.0),
+        TimeUnits::Minute => Time::new::<minute>(1.0),
+    };
+    Ok(data_frame
+        .lazy()
+        .select([
+            (col(&columns.retention_time).cast(DataType::Float64) * lit(time.get::<millisecond>()))
+                .round(0)
+                .cast(DataType::Int32)
+                .alias("RetentionTime"),
+            col(&columns.mass_to_charge)
+                .cast(DataType::Float32)
+                .alias("MassToCharge"),
+            col(&columns.signal).cast(DataType::Float32).alias("Signal"),
+        ])
+        .drop_nulls(Some(vec![col("RetentionTime")]))
+        .group_by_stable([col("RetentionTime")])
+        .agg([as_struct(vec![col("MassToCharge"), col("Signal")]).alias("MassSpectrum")])
+        .sort_by_exprs([col("RetentionTime")], Default::default())
+        .collect()?)
+}
+
+fn reader(bytes: &[u8], options: Options, rows: Option<usize>) -> CsvReader<Cursor<&[u8]>> {
+    CsvReadOptions::default()
+        .with_has_header(options.header)
+        .with_n_rows(rows)
+        .with_parse_options(
+            CsvParseOptions::default()
+                .with_separator(options.delimiter.byte())
+                .with_decimal_comma(options.decimal == Decimal::Comma),
+        )
+        .into_reader_with_file_handle(Cursor::new(bytes))
+}
+
+/// Delimiter
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(crate) enum Delimiter {
+    #[default]
+    Comma,
+    Semicolon,
+    Tab,
+}
+
+impl Delimiter {
+    pub(crate) const fn byte(self) -> u8 {
+        match self {
+            Self::Comma => b',',
+            Self::Semicolon => b';',
+            Self::Tab => b'\t',
+        }
+    }
+
+    pub(crate) const fn text(self) -> &'static str {
+        match self {
+            Self::Comma => "Comma",
+            Self::Semicolon => "Semicolon",
+            Self::Tab => "Tab",
+        }
+    }
+}
+
+/// Decimal separator
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(crate) enum Decimal {
+    #[default]
+    Point,
+    Comma,
+}
+
+impl Decimal {
+    pub(crate) const fn text(self) -> &'static str {
+        match self {
+            Self::Point => "Point",
+            Self::Comma => "Comma",
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use anyhow::{Context, Result};
+use egui::{emath::round_to_decimals, Color32, FontDefinitions};
+use quick_xml::escape::escape;
+use resvg::{
+    tiny_skia::{Pixmap, Transform},
+    usvg::{self, Tree},
+};
+use std::fmt::Write;
+
+/// Font of the exported figures (egui default proportional font)
+const FONT: &str = "Ubuntu-Light";
+const FONT_SIZE: f64 = 12.0;
+const TICKS: f64 = 6.0;
+/// Smallest drawn span relative to the magnitude of the bounds
+const RESOLUTION: f64 = 1e-9;
+/// Plot area margins: top, right, bottom, left
+const MARGINS: [f64; 4] = [16.0, 16.0, 48.0, 72.0];
+
+/// Plot figure, drawn on screen and exported to SVG and PNG
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Figure {
+    pub(crate) x_label: String,
+    pub(crate) y_label: String,
+    pub(crate) legend: bool,
+    /// Visible bounds `[min, max]` of `[x, y]`
+    pub(crate) bounds: Option<[[f64; 2]; 2]>,
+    pub(crate) items: Vec<Item>,
+}
+
+/// Figure item
+#[derive(Clone, Debug)]
+pub(crate) struct Item {
+    pub(crate) name: String,
+    pub(crate) color: Color32,
+    pub(crate) kind: Kind,
+}
+
+/// Figure item kind
+#[derive(Clone, Debug)]
+pub(crate) enum Kind {
+    Line(Vec<[f64; 2]>),
+    /// Bars `[x, y]` with a width and names
+    Bars {
+        width: f64,
+        bars: Vec<([f64; 2], String)>,
+    },
+}
+
+impl Figure {
+    /// Data bounds `[min, max]` of `[x, y]` (bars start at zero)
+    fn data_bounds(&self) -> [[f64; 2]; 2] {
+        let mut bounds = [[f64::INFINITY; 2], [f64::NEG_INFINITY; 2]];
+        let mut include = |[x, y]: [f64; 2]| {
+            bounds[0] = [bounds[0][0].min(x), bounds[0][1].min(y)];
+            bounds[1] = [bounds[1][0].max(x), bounds[1][1].max(y)];
+        };
+        for item in &self.items {
+            match &item.kind {
+                Kind::Line(points) => points.iter().copied().for_each(&mut include),
+                Kind::Bars { width, bars } => {
+                    for &([x, y], _) in bars {
+                        include([x - width / 2.0, 0.0]);
+                        include([x + width / 2.0, y]);
+                    }
+                }
+            }
+        }
+        if bounds[0][0] > bounds[1][0] {
+            return [[0.0; 2], [1.0; 2]];
+        }
+        bounds
+    }
+
+    /// Renders the figure as SVG of the size (in pixels).
+    pub(crate) fn svg(&self, [width, height]: [f64; 2]) -> String {
+        let [top, right, bottom, left] = MARGINS;
+        let area = [
+            left,
+            top,
+            (width - left - right).max(1.0),
+            (height - top - bottom).max(1.0),
+        ];
+        let [[x_min, y_min], [x_max, y_max]] = self.bounds.unwrap_or_else(|| self.data_bounds());
+        let [x_min, x_max] = range(x_min, x_max);
+        let [y_min, y_max] = range(y_min, y_max);
+        let x = |value: f64| area[0] + (value - x_min) / (x_max - x_min) * area[2];
+        let y = |value: f64| area[1] + area[3] - (value - y_min) / (y_max - y_min) * area[3];
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="Ubuntu, sans-serif" font-size="{FONT_SIZE}">"#,
+        )
+        .ok();
+        writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).ok();
+        // Grid and ticks
+        for value in ticks(x_min, x_max) {
+            let x = x(value);
+            writeln!(
+                svg,
+                r##"<line x1="{x:.2}" y1="{}" x2="{x:.2}" y2="{}" stroke="#e0e0e0"/>"##,
+                area[1],
+                area[1] + area[3],
+            )
+            .ok();
+            writeln!(
+                svg,
+                r#"<text x="{x:.2}" y="{}" text-anchor="middle">{}</text>"#,
+                area[1] + area[3] + FONT_SIZE * 1.5,
+                round_to_decimals(value, 5),
+            )
+            .ok();
+        }
+        for value in ticks(y_min, y_max) {
+            let y = y(value);
+            writeln!(
+                svg,
+                r##"<line x1="{}" y1="{y:.2}" x2="{}" y2="{y:.2}" stroke="#e0e0e0"/>"##,
+                area[0],
+                area[0] + area[2],
+            )
+            .ok();
+            writeln!(
+                svg,
+                r#"<text x="{}" y="{:.2}" text-anchor="end">{}</text>"#,
+                area[0] - FONT_SIZE / 2.0,
+                y + FONT_SIZE / 3.0,
+                round_to_decimals(value, 5),
+            )
+            .ok();
+        }
+        // Items (a nested viewport clips them to the plot area without ids, so
+        // that several figures can be inlined into one document)
+        writeln!(
+            svg,
+            r#"<svg x="{0}" y="{1}" width="{2}" height="{3}" viewBox="{0} {1} {2} {3}" fill="none">"#,
+            area[0], area[1], area[2], area[3],
+        )
+        .ok();
+        for item in &self.items {
+            let color = rgb(item.color);
+            match &item.kind {
+                Kind::Line(points) => {
+                    let points = points
+                        .iter()
+                        .map(|&[point_x, point_y]| format!("{:.2},{:.2}", x(point_x), y(point_y)))
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    writeln!(
+                        svg,
+                        r#"<polyline points="{points}" stroke="{color}" stroke-width="1"/>"#,
+                    )
+                    .ok();
+                }
+                Kind::Bars { width, bars } => {
+                    for &([bar_x, bar_y], _) in bars {
+                        let (bar_left, bar_right) =
+                            (x(bar_x - width / 2.0), x(bar_x + width / 2.0));
+                        let (bar_top, bar_bottom) = (y(bar_y.max(0.0)), y(bar_y.min(0.0)));
+                        writeln!(
+                            svg,
+                            r#"<rect x="{bar_left:.2}" y="{bar_top:.2}" width="{:.2}" height="{:.2}" fill="{color}" fill-opacity="0.2" stroke="{color}" stroke-width="1"/>"#,
+                            bar_right - bar_left,
+                            bar_bottom - bar_top,
+                        )
+                        .ok();
+                    }
+                }
+            }
+        }
+        writeln!(svg, "</svg>").ok();
+        // Frame and axis labels
+        writeln!(
+            svg,
+            r#"<rect x="{}" y="{}" width="{}" height="{}" fill="none" stroke="black"/>"#,
+            area[0], area[1], area[2], area[3],
+        )
+        .ok();
+        writeln!(
+            svg,
+            r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+            area[0] + area[2] / 2.0,
+            height - FONT_SIZE / 2.0,
+            escape(&self.x_label),
+        )
+        .ok();
+        writeln!(
+            svg,
+            r#"<text transform="translate({},{}) rotate(-90)" text-anchor="middle">{}</text>"#,
+            FONT_SIZE,
+            area[1] + area[3] / 2.0,
+            escape(&self.y_label),
+        )
+        .ok();
+        // Legend (named items, top right)
+        if self.legend {
+            let named = self.items.iter().filter(|item| !item.name.is_empty());
+            let entry = FONT_SIZE * 1.5;
+            let end = area[0] + area[2] - FONT_SIZE;
+            for (index, item) in named.enumerate() {
+                let y = area[1] + FONT_SIZE + index as f64 * entry;
+                writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                    end - FONT_SIZE / 3.0,
+                    y - FONT_SIZE / 3.0,
+                    FONT_SIZE / 3.0,
+                    rgb(item.color),
+                )
+                .ok();
+                writeln!(
+                    svg,
+                    r#"<text x="{}" y="{y}" text-anchor="end">{}</text>"#,
+                    end - FONT_SIZE,
+                    escape(&item.name),
+                )
+                .ok();
+            }
+        }
+        writeln!(svg, "</svg>").ok();
+        svg
+    }
+
+    /// Renders the figure as PNG of the size (in points) and scale (pixels
+    /// per point).
+    pub(crate) fn png(&self, size: [f64; 2], scale: f32) -> Result<Vec<u8>> {
+        let mut options = usvg::Options {
+            font_family: "Ubuntu".to_owned(),
+            ..Default::default()
+        };
+        let fonts = FontDefinitions::default();
+        if let Some(font) = fonts.font_data.get(FONT) {
+            options.fontdb_mut().load_font_data(font.font.to_vec());
+        }
+        let tree = Tree::from_str(&self.svg(size), &options)?;
+        let [width, height] = size.map(|size| (size * scale as f64).round() as u32);
+        let mut pixmap = Pixmap::new(width, height)
+            .with_context(|| format!("invalid image size {width}x{height}"))?;
+        resvg::render(
+            &tree,
+            Transform::from_scale(scale, scale),
+            &mut pixmap.as_mut(),
+        );
+        Ok(pixmap.encode_png()?)
+    }
+}
+
+/// Range padded around a single value (or a span below the float resolution
+/// at its magnitude), `[-1, 1]` without finite bounds
+fn range(min: f64, max: f64) -> [f64; 2] {
+    if !min.is_finite() || !max.is_finite() {
+        return [-1.0, 1.0];
+    }
+    let magnitude = min.abs().max(max.abs());
+    if max - min > magnitude * RESOLUTION {
+        return [min, max];
+    }
+    let padding = if magnitude > 0.0 {
+        magnitude / 20.0
+    } else {
+        1.0
+    };
+    [min - padding, max + padding]
+}
+
+/// Round tick values (1, 2 or 5 times a power of ten apart)
+fn ticks(min: f64, max: f64) -> impl Iterator<Item = f64> {
+    let step = (max - min) / TICKS;
+    let magnitude = 10f64.powf(step.log10().floor());
+    let step = match step / magnitude {
+        normalized if normalized < 1.5 => magnitude,
+        normalized if normalized < 3.5 => 2.0 * magnitude,
+        normalized if normalized < 7.5 => 5.0 * magnitude,
+        _ => 10.0 * magnitude,
+    };
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(move |index| index as f64 * step)
+}
+
+fn rgb(color: Color32) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r(), color.g(), color.b())
+}
@@ -0,0 +1,285 @@
+use super::{data_frame, Spectrum};
+use crate::app::data::Metadata;
+use anyhow::{ensure, Context, Result};
+use polars::frame::DataFrame;
+use std::{fmt::Write, mem::take};
+use tracing::info;
+use uom::si::{
+    f64::Time,
+    time::{millisecond, minute, second},
+};
+
+// http://www.jcamp-dx.org/protocols.html
+// https://doi.org/10.1366/0003702934067694 (JCAMP-DX for mass spectrometry)
+
+/// Reads JCAMP-DX mass spectra (single, `LINK` blocks or `NTUPLES` pages)
+/// into a data frame with the first block labels as metadata.
+pub(crate) fn read(text: &str) -> Result<(DataFrame, Metadata)> {
+    let mut metadata = Metadata::new();
+    let mut spectra = Vec::new();
+    let mut block = Block::default();
+    for (index, line) in text.lines().enumerate() {
+        // Comments
+        let line = line.split("$$").next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some(record) = line.strip_prefix("##") else {
+            if block.table {
+                block
+                    .values(line)
+                    .with_context(|| format!("invalid peak table line {}", index + 1))?;
+            }
+            continue;
+        };
+        let (label, value) = record.split_once('=').unwrap_or((record, ""));
+        let value = value.trim();
+        block.table = false;
+        match &*normalize(label) {
+            "PEAKTABLE" | "DATATABLE" => block.table = true,
+            "XFACTOR" => block.x_factor = number(label, value)?,
+            "YFACTOR" => block.y_factor = number(label, value)?,
+            "RETENTIONTIME" | "$RETENTIONTIME" => {
+                block.retention_time = Some(Time::new::<second>(number(label, value)?));
+            }
+            "UNITS" => block.minutes = value.to_ascii_uppercase().contains("MINUTE"),
+            "PAGE" => {
+                block.flush(&mut spectra)?;
+                // `##PAGE= T= 1.25` or `##PAGE= 1.25`
+                let value = value.trim_start_matches(|char: char| !char.is_ascii_digit());
+                if let Ok(value) = value.trim().parse() {
+                    block.retention_time = Some(if block.minutes {
+                        Time::new::<minute>(value)
+                    } else {
+                        Time::new::<second>(value)
+                    });
+                }
+            }
+            "ENDNTUPLES" => block.flush(&mut spectra)?,
+            "END" => {
+                block.flush(&mut spectra)?;
+                block = Block::default();
+            }
+            _ => {
+                if spectra.is_empty() {
+                    metadata
+                        .entry(label.trim().to_owned())
+                        .or_insert_with(|| value.to_owned());
+                }
+            }
+        }
+    }
+    block.flush(&mut spectra)?;
+    ensure!(!spectra.is_empty(), "no mass spectrum peak table found");
+    info!(spectra = spectra.len(), "JCAMP-DX");
+    Ok((data_frame(&spectra)?, metadata))
+}
+
+/// Writes a mass spectrum as JCAMP-DX peak table.
+pub(crate) fn write(title: &str, spectrum: &Spectrum) -> String {
+    let retention_time = Time::new::<millisecond>(spectrum.retention_time);
+    let mut text = String::new();
+    writeln!(text, "##TITLE={title}").ok();
+    writeln!(text, "##JCAMP-DX=5.01").ok();
+    writeln!(text, "##DATA TYPE=MASS SPECTRUM").ok();
+    writeln!(text, "##DATA CLASS=PEAK TABLE").ok();
+    writeln!(text, "##ORIGIN=MS viewer {}", env!("CARGO_PKG_VERSION")).ok();
+    writeln!(text, "##OWNER=").ok();
+    writeln!(text, "##SPECTROMETER/DATA SYSTEM=MS viewer").ok();
+    writeln!(text, "##RETENTION_TIME={}", retention_time.get::<second>()).ok();
+    writeln!(text, "##XUNITS=M/Z").ok();
+    writeln!(text, "##YUNITS=ARBITRARY UNITS").ok();
+    writeln!(text, "##NPOINTS={}", spectrum.signal.len()).ok();
+    writeln!(text, "##PEAK TABLE=(XY..XY)").ok();
+    for (mass_to_charge, signal) in spectrum.mass_to_charge.iter().zip(&spectrum.signal) {
+        writeln!(text, "{mass_to_charge},{signal}").ok();
+    }
+    writeln!(text, "##END=").ok();
+    text
+}
+
+/// Data block (spectrum) state
+#[derive(Clone, Debug)]
+struct Block {
+    table: bool,
+    minutes: bool,
+    x_factor: f64,
+    y_factor: f64,
+    retention_time: Option<Time>,
+    values: Vec<f64>,
+}
+
+impl Default for Block {
+    fn default() -> Self {
+        Self {
+            table: false,
+            minutes: false,
+            x_factor: 1.0,
+            y_factor: 1.0,
+            retention_time: None,
+            values: Vec::new(),
+        }
+    }
+}
+
+impl Block {
+    /// Parses `x,y x,y` / `x, y; x, y` pairs (AFFN)
+    fn values(&mut self, line: &str) -> Result<()> {
+        for value in line
+            .split(|char: char| char.is_whitespace() || char == ',' || char == ';')
+            .filter(|value| !value.is_empty())
+        {
+            self.values.push(value.parse()?);
+        }
+        Ok(())
+    }
+
+    /// Pushes the peak table as a spectrum, an unpaired value is an error.
+    fn flush(&mut self, spectra: &mut Vec<Spectrum>) -> Result<()> {
+        if self.values.is_empty() {
+            return Ok(());
+        }
+        let values = take(&mut self.values);
+        ensure!(
+            values.len() % 2 == 0,
+            "peak table of spectrum {} has an unpaired value {}",
+            spectra.len() + 1,
+            values[values.len() - 1],
+        );
+        let pairs = values.chunks_exact(2);
+        spectra.push(Spectrum {
+            retention_time: self
+                .retention_time
+                .map_or(0.0, |retention_time| retention_time.get::<millisecond>()),
+            mass_to_charge: pairs
+                .clone()
+                .map(|pair| (pair[0] * self.x_factor) as _)
+                .collect(),
+            signal: pairs.map(|pair| (pair[1] * self.y_factor) as _).collect(),
+        });
+        Ok(())
+    }
+}
+
+/// Label normalized per JCAMP-DX (case, spaces, `-`, `/` and `_` ignored)
+fn normalize(label: &str) -> String {
+    label
+        .chars()
+        .filter(|char| !matches!(char, ' ' | '-' | '/' | '_'))
+        .map(|char| char.to_ascii_uppercase())
+        .collect()
+}
+
+fn number(label: &str, value: &str) -> Result<f64> {
+    value
+        .parse()
+        .with_context(|| format!("invalid {label} value {value:?}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::formats::spectra;
+
+    /// Single spectrum (comments, factors, mixed pair separators)
+    const SPECTRUM: &str = r#"##TITLE=Benzene
+##JCAMP-DX=4.24
+##DATA TYPE=MASS SPECTRUM
+##ORIGIN=NIST $$ comment
+##OWNER=public
+##MOLFORM=C6 H6
+##CAS REGISTRY NO=71-43-2
+##XUNITS=M/Z
+##YUNITS=RELATIVE ABUNDANCE
+##XFACTOR=1
+##YFACTOR=2
+##NPOINTS=4
+##PEAK TABLE=(XY..XY)
+50,160 51,190
+78,9999; 79, 650
+##END="#;
+
+    /// `LINK` blocks with retention times
+    const LINK: &str = r#"##TITLE=GC-MS run
+##JCAMP-DX=5.00
+##DATA TYPE=LINK
+##BLOCKS=2
+##TITLE=scan 1
+##JCAMP-DX=5.00
+##DATA TYPE=MASS SPECTRUM
+##BLOCK_ID=1
+##RETENTION_TIME=12.5
+##PEAK TABLE=(XY..XY)
+50,1 51,2
+##END=
+##TITLE=scan 2
+##JCAMP-DX=5.00
+##DATA TYPE=MASS SPECTRUM
+##BLOCK_ID=2
+##$RETENTION TIME=13
+##PEAK TABLE=(XY..XY)
+60,3
+##END=
+##END="#;
+
+    #[test]
+    fn read_spectrum() -> Result<()> {
+        let (data_frame, metadata) = super::read(SPECTRUM)?;
+        assert_eq!(
+            spectra(&data_frame)?,
+            [Spectrum {
+                retention_time: 0.0,
+                mass_to_charge: vec![50.0, 51.0, 78.0, 79.0],
+                signal: vec![320.0, 380.0, 19998.0, 1300.0],
+            }],
+        );
+        assert_eq!(metadata["TITLE"], "Benzene");
+        assert_eq!(metadata["ORIGIN"], "NIST");
+        Ok(())
+    }
+
+    #[test]
+    fn read_link() -> Result<()> {
+        let (data_frame, metadata) = super::read(LINK)?;
+        assert_eq!(
+            spectra(&data_frame)?,
+            [
+                Spectrum {
+                    retention_time: 12500.0,
+                    mass_to_charge: vec![50.0, 51.0],
+                    signal: vec![1.0, 2.0],
+                },
+                Spectrum {
+                    retention_time: 13000.0,
+                    mass_to_charge: vec![60.0],
+                    signal: vec![3.0],
+                },
+            ],
+        );
+        assert_eq!(metadata["TITLE"], "GC-MS run");
+        Ok(())
+    }
+
+    #[test]
+    fn unpaired() {
+        let jcamp = "##TITLE=Benzene\n##PEAK TABLE=(XY..XY)\n50,160 51,190\n78\n##END=";
+        let error = super::read(jcamp).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "peak table of spectrum 1 has an unpaired value 78",
+        );
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let spectrum = Spectrum {
+            retention_time: 61500.0,
+            mass_to_charge: vec![50.5, 73.0, 147.1],
+            signal: vec![10.0, 2000.0, 0.25],
+        };
+        let (data_frame, metadata) = super::read(&write("Run", &spectrum))?;
+        assert_eq!(spectra(&data_frame)?, [spectrum]);
+        assert_eq!(metadata["TITLE"], "Run");
+        Ok(())
+    }
+}
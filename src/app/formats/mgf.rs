@@ -0,0 +1,296 @@
+use super::{mass_spectrum, Spectrum};
+use crate::app::data::Metadata;
+use anyhow::{ensure, Context, Result};
+use polars::prelude::*;
+use std::{fmt::Write, iter::zip, mem::take};
+use tracing::info;
+use uom::si::{
+    f64::Time,
+    time::{millisecond, second},
+};
+
+// https://www.matrixscience.com/help/data_file_help.html#GEN (Mascot generic format)
+
+/// MGF `BEGIN IONS`/`END IONS` block
+#[derive(Clone, Debug, Default)]
+struct Ions {
+    title: Option<String>,
+    pep_mass: Option<f64>,
+    charge: Option<String>,
+    /// Retention time in milliseconds
+    retention_time: Option<f64>,
+    spectrum: Spectrum,
+}
+
+/// Reads MGF into a data frame with `RetentionTime`, `Title`, `PepMass`,
+/// `Charge` and `MassSpectrum` columns and the global parameters as metadata.
+pub(crate) fn read(text: &str) -> Result<(DataFrame, Metadata)> {
+    let mut metadata = Metadata::new();
+    let mut blocks = Vec::new();
+    let mut ions: Option<Ions> = None;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        // Comments
+        if line.is_empty() || line.starts_with(['#', ';', '!', '/']) {
+            continue;
+        }
+        match &*line.to_ascii_uppercase() {
+            "BEGIN IONS" => {
+                ensure!(ions.is_none(), "unclosed ions block on line {}", index + 1);
+                ions = Some(Default::default());
+                continue;
+            }
+            "END IONS" => {
+                let ions = ions
+                    .take()
+                    .with_context(|| format!("unexpected end of ions on line {}", index + 1))?;
+                blocks.push(ions);
+                continue;
+            }
+            _ => {}
+        }
+        let Some(ions) = &mut ions else {
+            // Global parameters
+            if let Some((key, value)) = line.split_once('=') {
+                metadata.insert(key.trim().to_owned(), value.trim().to_owned());
+            }
+            continue;
+        };
+        if line.starts_with(|char: char| char.is_ascii_digit() || char == '.') {
+            ions.peak(line)
+                .with_context(|| format!("invalid peak on line {}", index + 1))?;
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match &*key.trim().to_ascii_uppercase() {
+            "TITLE" => ions.title = Some(value.to_owned()),
+            // `PEPMASS=500.25 1200` (m/z and optional intensity)
+            "PEPMASS" => {
+                let pep_mass = value.split_whitespace().next().unwrap_or_default();
+                ions.pep_mass = Some(number(key, pep_mass)?);
+            }
+            "CHARGE" => ions.charge = Some(value.to_owned()),
+            // `RTINSECONDS=120.5` or a range `RTINSECONDS=120-125`
+            "RTINSECONDS" => {
+                let seconds = value.split('-').next().unwrap_or_default().trim();
+                let retention_time = Time::new::<second>(number(key, seconds)?);
+                ions.retention_time = Some(retention_time.get::<millisecond>());
+            }
+            _ => {}
+        }
+    }
+    ensure!(ions.is_none(), "unclosed ions block");
+    info!(spectra = blocks.len(), "MGF");
+    // Global charge applies to blocks without their own
+    if let Some(charge) = metadata.get("CHARGE") {
+        for ions in blocks.iter_mut().filter(|ions| ions.charge.is_none()) {
+            ions.charge = Some(charge.clone());
+        }
+    }
+    let spectra = blocks
+        .iter_mut()
+        .map(|ions| take(&mut ions.spectrum))
+        .collect::<Vec<_>>();
+    let data_frame = DataFrame::new(vec![
+        Series::new(
+            "RetentionTime",
+            blocks
+                .iter()
+                .map(|ions| {
+                    ions.retention_time
+                        .map(|retention_time| retention_time.round() as i32)
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "Title",
+            blocks
+                .iter()
+                .map(|ions| ions.title.as_deref())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "PepMass",
+            blocks.iter().map(|ions| ions.pep_mass).collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "Charge",
+            blocks
+                .iter()
+                .map(|ions| ions.charge.as_deref())
+                .collect::<Vec<_>>(),
+        ),
+        mass_spectrum(&spectra)?,
+    ])?;
+    Ok((data_frame, metadata))
+}
+
+/// Writes the data frame rows as MGF ions blocks (`Title` or `Name`,
+/// `PepMass`, `Charge` and `RetentionTime` columns are optional).
+pub(crate) fn write(data_frame: &DataFrame) -> Result<String> {
+    let mut text = String::new();
+    for ions in blocks(data_frame)? {
+        writeln!(text, "BEGIN IONS").ok();
+        if let Some(title) = &ions.title {
+            writeln!(text, "TITLE={title}").ok();
+        }
+        if let Some(pep_mass) = ions.pep_mass {
+            writeln!(text, "PEPMASS={pep_mass}").ok();
+        }
+        if let Some(charge) = &ions.charge {
+            writeln!(text, "CHARGE={charge}").ok();
+        }
+        if let Some(retention_time) = ions.retention_time {
+            let retention_time = Time::new::<millisecond>(retention_time);
+            writeln!(text, "RTINSECONDS={}", retention_time.get::<second>()).ok();
+        }
+        let spectrum = &ions.spectrum;
+        for (mass_to_charge, signal) in spectrum.mass_to_charge.iter().zip(&spectrum.signal) {
+            writeln!(text, "{mass_to_charge} {signal}").ok();
+        }
+        writeln!(text, "END IONS").ok();
+        writeln!(text).ok();
+    }
+    Ok(text)
+}
+
+fn blocks(data_frame: &DataFrame) -> Result<Vec<Ions>> {
+    let optional = |name| data_frame.column(name).ok();
+    let retention_time = optional("RetentionTime").map(Series::i32).transpose()?;
+    let title = optional("Title")
+        .or_else(|| optional("Name"))
+        .map(Series::str)
+        .transpose()?;
+    let pep_mass = optional("PepMass").map(Series::f64).transpose()?;
+    let charge = optional("Charge").map(Series::str).transpose()?;
+    let mass_spectrum = data_frame["MassSpectrum"].list()?;
+    let mut blocks = Vec::with_capacity(data_frame.height());
+    for row_index in 0..data_frame.height() {
+        let Some(peaks) = mass_spectrum.get_as_series(row_index) else {
+            continue;
+        };
+        let peaks = peaks.struct_()?;
+        let mass_to_charge = peaks.field_by_name("MassToCharge")?;
+        let signal = peaks.field_by_name("Signal")?.cast(&DataType::Float32)?;
+        let (mass_to_charge, signal) = zip(mass_to_charge.f32()?, signal.f32()?)
+            .filter_map(|(mass_to_charge, signal)| Some((mass_to_charge?, signal?)))
+            .unzip();
+        let retention_time = retention_time
+            .and_then(|retention_time| retention_time.get(row_index))
+            .map(f64::from);
+        blocks.push(Ions {
+            title: title
+                .and_then(|title| title.get(row_index))
+                .map(ToOwned::to_owned),
+            pep_mass: pep_mass.and_then(|pep_mass| pep_mass.get(row_index)),
+            charge: charge
+                .and_then(|charge| charge.get(row_index))
+                .map(ToOwned::to_owned),
+            retention_time,
+            spectrum: Spectrum {
+                retention_time: retention_time.unwrap_or_default(),
+                mass_to_charge,
+                signal,
+            },
+        });
+    }
+    Ok(blocks)
+}
+
+impl Ions {
+    /// Parses a `m/z intensity [charge]` line
+    fn peak(&mut self, line: &str) -> Result<()> {
+        let mut values = line.split_whitespace();
+        let mass_to_charge = values.next().context("missing m/z")?.parse()?;
+        let signal = values.next().context("missing intensity")?.parse()?;
+        self.spectrum.mass_to_charge.push(mass_to_charge);
+        self.spectrum.signal.push(signal);
+        Ok(())
+    }
+}
+
+fn number(key: &str, value: &str) -> Result<f64> {
+    value
+        .parse()
+        .with_context(|| format!("invalid {key} value {value:?}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::formats::spectra;
+
+    /// Global charge, comments, a retention time range and a block without
+    /// retention time
+    const MGF: &str = r#"# Exported spectra
+COM=Test run
+CHARGE=2+
+
+BEGIN IONS
+TITLE=Scan 1
+PEPMASS=500.25 1200
+RTINSECONDS=61.5-62
+50.5 10
+73 2000 1+
+END IONS
+
+BEGIN IONS
+TITLE=Scan 2
+PEPMASS=600.5
+CHARGE=3+
+147.1 500
+END IONS
+"#;
+
+    #[test]
+    fn read() -> Result<()> {
+        let (data_frame, metadata) = super::read(MGF)?;
+        assert_eq!(
+            data_frame["RetentionTime"]
+                .i32()?
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [Some(61500), None],
+        );
+        assert_eq!(
+            data_frame["Title"].str()?.into_iter().collect::<Vec<_>>(),
+            [Some("Scan 1"), Some("Scan 2")],
+        );
+        assert_eq!(
+            data_frame["PepMass"].f64()?.into_iter().collect::<Vec<_>>(),
+            [Some(500.25), Some(600.5)],
+        );
+        assert_eq!(
+            data_frame["Charge"].str()?.into_iter().collect::<Vec<_>>(),
+            [Some("2+"), Some("3+")],
+        );
+        assert_eq!(
+            spectra(&data_frame)?,
+            [
+                Spectrum {
+                    retention_time: 61500.0,
+                    mass_to_charge: vec![50.5, 73.0],
+                    signal: vec![10.0, 2000.0],
+                },
+                Spectrum {
+                    retention_time: 0.0,
+                    mass_to_charge: vec![147.1],
+                    signal: vec![500.0],
+                },
+            ],
+        );
+        assert_eq!(metadata["COM"], "Test run");
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let (data_frame, _) = super::read(MGF)?;
+        let (written, _) = super::read(&write(&data_frame)?)?;
+        assert!(written.equals_missing(&data_frame));
+        Ok(())
+    }
+}
@@ -0,0 +1,134 @@
+pub(crate) use self::{
+    andi::read as andi,
+    chemstation::read as chemstation,
+    compression::{compress, decompress},
+    jcamp::{read as jcamp, write as to_jcamp},
+    mgf::{read as mgf, write as to_mgf},
+    msp::{read as msp, write as to_msp},
+    mzml::{read as mzml, write as to_mzml, Bits, Options as MzMlOptions},
+    mzxml::read as mzxml,
+    registry::{Format, Scope},
+    xlsx::write as to_xlsx,
+};
+
+use anyhow::Result;
+use base64::{prelude::BASE64_STANDARD, Engine};
+use flate2::read::ZlibDecoder;
+use polars::{chunked_array::builder::get_list_builder, prelude::*};
+use quick_xml::events::BytesStart;
+use std::io::Read;
+
+/// Spectrum
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Spectrum {
+    /// Retention time in milliseconds
+    pub(crate) retention_time: f64,
+    pub(crate) mass_to_charge: Vec<f32>,
+    pub(crate) signal: Vec<f32>,
+}
+
+/// Peak data type (`MassSpectrum` list item)
+pub(crate) fn peak() -> DataType {
+    DataType::Struct(vec![
+        Field::new("MassToCharge", DataType::Float32),
+        Field::new("Signal", DataType::Float32),
+    ])
+}
+
+/// Data frame with `RetentionTime` and `MassSpectrum` columns
+pub(crate) fn data_frame(spectra: &[Spectrum]) -> PolarsResult<DataFrame> {
+    let retention_time = Series::new(
+        "RetentionTime",
+        spectra
+            .iter()
+            .map(|spectrum| spectrum.retention_time.round() as i32)
+            .collect::<Vec<_>>(),
+    );
+    DataFrame::new(vec![retention_time, mass_spectrum(spectra)?])
+}
+
+/// `MassSpectrum` column
+pub(crate) fn mass_spectrum(spectra: &[Spectrum]) -> PolarsResult<Series> {
+    let values_capacity = spectra.iter().map(|spectrum| spectrum.signal.len()).sum();
+    let mut builder = get_list_builder(&peak(), values_capacity, spectra.len(), "MassSpectrum")?;
+    for spectrum in spectra {
+        let fields = [
+            Series::new("MassToCharge", &spectrum.mass_to_charge),
+            Series::new("Signal", &spectrum.signal),
+        ];
+        builder.append_series(&StructChunked::from_series("", &fields)?.into_series())?;
+    }
+    Ok(builder.finish().into_series())
+}
+
+/// Spectra of the `MassSpectrum` column, rows without a mass spectrum are
+/// skipped (retention time is zero without the `RetentionTime` column)
+fn spectra(data_frame: &DataFrame) -> Result<Vec<Spectrum>> {
+    let retention_time = data_frame
+        .column("RetentionTime")
+        .ok()
+        .map(Series::i32)
+        .transpose()?;
+    let mass_spectrum = data_frame["MassSpectrum"].list()?;
+    let mut spectra = Vec::with_capacity(data_frame.height());
+    for row_index in 0..data_frame.height() {
+        let Some(peaks) = mass_spectrum.get_as_series(row_index) else {
+            continue;
+        };
+        let peaks = peaks.struct_()?;
+        let mass_to_charge = peaks
+            .field_by_name("MassToCharge")?
+            .cast(&DataType::Float32)?;
+        let signal = peaks.field_by_name("Signal")?.cast(&DataType::Float32)?;
+        let (mass_to_charge, signal) = mass_to_charge
+            .f32()?
+            .into_iter()
+            .zip(signal.f32()?)
+            .filter_map(|(mass_to_charge, signal)| Some((mass_to_charge?, signal?)))
+            .unzip();
+        spectra.push(Spectrum {
+            retention_time: retention_time
+                .and_then(|retention_time| retention_time.get(row_index))
+                .map_or(0.0, f64::from),
+            mass_to_charge,
+            signal,
+        });
+    }
+    Ok(spectra)
+}
+
+/// Unescaped XML attribute value
+fn attribute(start: &BytesStart, name: &str) -> Result<Option<String>> {
+    Ok(match start.try_get_attribute(name)? {
+        Some(attribute) => Some(attribute.unescape_value()?.into_owned()),
+        None => None,
+    })
+}
+
+/// Decodes base64 text (whitespace is ignored), inflating zlib compressed
+/// content
+fn decode(text: &str, zlib: bool) -> Result<Vec<u8>> {
+    let text: String = text.split_whitespace().collect();
+    let bytes = BASE64_STANDARD.decode(text)?;
+    if !zlib {
+        return Ok(bytes);
+    }
+    let mut decompressed = Vec::new();
+    ZlibDecoder::new(&*bytes).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+mod andi;
+mod chemstation;
+mod compression;
+pub(crate) mod csv;
+pub(crate) mod figure;
+mod jcamp;
+mod mgf;
+mod msp;
+mod mzml;
+mod mzxml;
+mod netcdf;
+mod registry;
+pub(crate) mod report;
+mod xlsx;
@@ -0,0 +1,250 @@
+use super::{mass_spectrum, Spectrum};
+use anyhow::{ensure, Context, Result};
+use polars::prelude::*;
+use std::fmt::Write;
+use tracing::{info, warn};
+
+// https://chemdata.nist.gov/dokuwiki/doku.php?id=chemdata:nist17 (NIST MSP)
+// https://mona.fiehnlab.ucdavis.edu/downloads (MoNA MSP)
+
+/// Reads an MSP spectral library into a data frame with `Name`, `Formula`,
+/// `MolecularWeight`, `Cas`, `RetentionIndex` and `MassSpectrum` columns.
+pub(crate) fn read(text: &str) -> Result<DataFrame> {
+    let mut records = Vec::new();
+    let mut record: Option<Record> = None;
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        // Peaks
+        if line.starts_with(|char: char| char.is_ascii_digit() || char == '.') {
+            let record = record
+                .as_mut()
+                .with_context(|| format!("peaks before name on line {}", index + 1))?;
+            record
+                .peaks(line)
+                .with_context(|| format!("invalid peaks on line {}", index + 1))?;
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            warn!(line = index + 1, "unexpected MSP line");
+            continue;
+        };
+        let value = value.trim();
+        match &*key.trim().to_lowercase() {
+            "name" => {
+                if let Some(record) = record.take() {
+                    records.push(record.validated()?);
+                }
+                record = Some(Record {
+                    name: value.to_owned(),
+                    ..Default::default()
+                });
+            }
+            key => {
+                let Some(record) = &mut record else {
+                    continue;
+                };
+                match key {
+                    "formula" => record.formula = Some(value.to_owned()),
+                    "mw" => record.molecular_weight = value.parse().ok(),
+                    "num peaks" => {
+                        let count = value.parse().with_context(|| {
+                            format!("invalid Num Peaks {value:?} of record {:?}", record.name)
+                        })?;
+                        record.count = Some(count);
+                    }
+                    // `CAS#: 71-43-2; NIST#: 12345`
+                    "cas#" | "casno" => {
+                        let cas = value.split(';').next().unwrap_or_default().trim();
+                        record.cas = Some(cas.to_owned());
+                    }
+                    "ri" | "retention_index" | "retentionindex" => {
+                        record.retention_index = value.parse().ok();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    if let Some(record) = record {
+        records.push(record.validated()?);
+    }
+    info!(records = records.len(), "MSP");
+    let spectra = records
+        .iter()
+        .map(|record| record.spectrum.clone())
+        .collect::<Vec<_>>();
+    Ok(DataFrame::new(vec![
+        Series::new(
+            "Name",
+            records
+                .iter()
+                .map(|record| record.name.as_str())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "Formula",
+            records
+                .iter()
+                .map(|record| record.formula.as_deref())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "MolecularWeight",
+            records
+                .iter()
+                .map(|record| record.molecular_weight)
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "Cas",
+            records
+                .iter()
+                .map(|record| record.cas.as_deref())
+                .collect::<Vec<_>>(),
+        ),
+        Series::new(
+            "RetentionIndex",
+            records
+                .iter()
+                .map(|record| record.retention_index)
+                .collect::<Vec<_>>(),
+        ),
+        mass_spectrum(&spectra)?,
+    ])?)
+}
+
+/// Writes a mass spectrum as an MSP record (NIST MS Search peak list).
+pub(crate) fn write(name: &str, spectrum: &Spectrum) -> String {
+    let mut text = String::new();
+    writeln!(text, "Name: {name}").ok();
+    writeln!(text, "Num Peaks: {}", spectrum.signal.len()).ok();
+    for (mass_to_charge, signal) in spectrum.mass_to_charge.iter().zip(&spectrum.signal) {
+        writeln!(text, "{mass_to_charge} {signal}").ok();
+    }
+    text
+}
+
+/// Library record
+#[derive(Clone, Debug, Default)]
+struct Record {
+    name: String,
+    formula: Option<String>,
+    molecular_weight: Option<f32>,
+    cas: Option<String>,
+    retention_index: Option<f32>,
+    /// `Num Peaks`
+    count: Option<usize>,
+    spectrum: Spectrum,
+}
+
+impl Record {
+    /// Checks the peak count against `Num Peaks`
+    fn validated(self) -> Result<Self> {
+        if let Some(count) = self.count {
+            ensure!(
+                self.spectrum.signal.len() == count,
+                "record {:?} has {} peaks, Num Peaks is {count}",
+                self.name,
+                self.spectrum.signal.len(),
+            );
+        }
+        Ok(self)
+    }
+
+    /// Parses `50 160; 51 190`, `50:160 51:190` or `50\t160 "annotation"`
+    fn peaks(&mut self, line: &str) -> Result<()> {
+        let mut values = Vec::new();
+        for (index, part) in line.split('"').enumerate() {
+            // Quoted annotations
+            if index % 2 == 1 {
+                continue;
+            }
+            for value in part
+                .split(|char: char| char.is_whitespace() || matches!(char, ';' | ',' | ':'))
+                .filter(|value| !value.is_empty())
+            {
+                values.push(value.parse::<f32>()?);
+            }
+        }
+        for pair in values.chunks_exact(2) {
+            self.spectrum.mass_to_charge.push(pair[0]);
+            self.spectrum.signal.push(pair[1]);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::formats::spectra;
+
+    /// NIST and MoNA records (peak separators, annotations)
+    const MSP: &str = r#"Name: Benzene
+Formula: C6H6
+MW: 78
+CAS#: 71-43-2; NIST#: 12345
+Num Peaks: 3
+50 160; 51 190;
+78 9999;
+
+Name: Toluene
+RI: 1042.5
+Num Peaks: 2
+91	9999	"C7H7+"
+92:650
+"#;
+
+    #[test]
+    fn read() -> Result<()> {
+        let data_frame = super::read(MSP)?;
+        assert_eq!(
+            data_frame["Name"].str()?.into_iter().collect::<Vec<_>>(),
+            [Some("Benzene"), Some("Toluene")],
+        );
+        assert_eq!(
+            data_frame["Formula"].str()?.into_iter().collect::<Vec<_>>(),
+            [Some("C6H6"), None],
+        );
+        assert_eq!(
+            data_frame["Cas"].str()?.into_iter().collect::<Vec<_>>(),
+            [Some("71-43-2"), None],
+        );
+        assert_eq!(
+            data_frame["RetentionIndex"]
+                .f32()?
+                .into_iter()
+                .collect::<Vec<_>>(),
+            [None, Some(1042.5)],
+        );
+        assert_eq!(
+            spectra(&data_frame)?,
+            [
+                Spectrum {
+                    retention_time: 0.0,
+                    mass_to_charge: vec![50.0, 51.0, 78.0],
+                    signal: vec![160.0, 190.0, 9999.0],
+                },
+                Spectrum {
+                    retention_time: 0.0,
+                    mass_to_charge: vec![91.0, 92.0],
+                    signal: vec![9999.0, 650.0],
+                },
+            ],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn num_peaks() {
+        let msp = "Name: Benzene\nNum Peaks: 3\n50 160; 51 190\n";
+        let error = super::read(msp).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            r#"record "Benzene" has 2 peaks, Num Peaks is 3"#,
+        );
+    }
+}
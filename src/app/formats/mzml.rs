@@ -0,0 +1,765 @@
+use super::{attribute, data_frame, decode, spectra, Format, Spectrum};
+use crate::app::{computers::is_library, data::Data};
+use anyhow::{bail, ensure, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use flate2::write::ZlibEncoder;
+use polars::frame::DataFrame;
+use quick_xml::{
+    escape::escape,
+    events::{BytesStart, Event},
+    Reader,
+};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write as _},
+    io::Write,
+};
+use tracing::{info, warn};
+use uom::si::{
+    f64::Time,
+    time::{millisecond, minute, second},
+};
+
+// https://www.psidev.info/mzML
+// https://raw.githubusercontent.com/HUPO-PSI/psi-ms-CV/master/psi-ms.obo
+
+const MS_LEVEL: &str = "MS:1000511";
+const SCAN_START_TIME: &str = "MS:1000016";
+const MS1_SPECTRUM: &str = "MS:1000579";
+const TOTAL_ION_CURRENT: &str = "MS:1000285";
+const BASE_PEAK_MASS_TO_CHARGE: &str = "MS:1000504";
+const BASE_PEAK_INTENSITY: &str = "MS:1000505";
+const LOWEST_OBSERVED_MASS_TO_CHARGE: &str = "MS:1000528";
+const HIGHEST_OBSERVED_MASS_TO_CHARGE: &str = "MS:1000527";
+const NO_COMBINATION: &str = "MS:1000795";
+const CONVERSION_TO_MZML: &str = "MS:1000544";
+const CUSTOM_UNRELEASED_SOFTWARE_TOOL: &str = "MS:1000799";
+const MASS_TO_CHARGE: &str = "MS:1000040";
+const NUMBER_OF_DETECTOR_COUNTS: &str = "MS:1000131";
+
+const MASS_SPECTROMETER_FILE_FORMAT: &str = "MS:1000560";
+const MZML_FORMAT: &str = "MS:1000584";
+const ISB_MZXML_FORMAT: &str = "MS:1000566";
+const MASCOT_MGF_FORMAT: &str = "MS:1001062";
+/// `scan=N` spectrum ids
+const SCAN_NUMBER_ONLY_NATIVE_ID_FORMAT: &str = "MS:1000776";
+
+/// Schemas of `mzML` and of the `indexedmzML` wrapper (same version)
+const SCHEMA: &str = "http://psidev.info/files/ms/mzML/xsd/mzML1.1.0.xsd";
+const INDEXED_SCHEMA: &str = "http://psidev.info/files/ms/mzML/xsd/mzML1.1.0_idx.xsd";
+
+const MASS_TO_CHARGE_ARRAY: &str = "MS:1000514";
+const INTENSITY_ARRAY: &str = "MS:1000515";
+
+const INTEGER32: &str = "MS:1000519";
+const FLOAT32: &str = "MS:1000521";
+const INTEGER64: &str = "MS:1000522";
+const FLOAT64: &str = "MS:1000523";
+
+const ZLIB_COMPRESSION: &str = "MS:1000574";
+const NO_COMPRESSION: &str = "MS:1000576";
+const NUMPRESS: [&str; 6] = [
+    "MS:1002312",
+    "MS:1002313",
+    "MS:1002314",
+    "MS:1002746",
+    "MS:1002747",
+    "MS:1002748",
+];
+
+const MILLISECOND: &str = "UO:0000028";
+const SECOND: &str = "UO:0000010";
+const MINUTE: &str = "UO:0000031";
+
+/// Reads mzML into a data frame with MS1 spectra.
+pub(crate) fn read(bytes: &[u8]) -> Result<DataFrame> {
+    let mut reader = Reader::from_reader(bytes);
+    reader.config_mut().trim_text(true);
+    let mut path = Vec::new();
+    let mut groups = HashMap::<String, Vec<CvParam>>::new();
+    let mut group = None;
+    let mut spectrum = None;
+    let mut spectra = Vec::new();
+    let mut skipped = 0;
+    loop {
+        let event = reader.read_event()?;
+        match &event {
+            Event::Start(start) | Event::Empty(start) => {
+                let parent = path.last().map(String::as_str);
+                match start.local_name().as_ref() {
+                    b"referenceableParamGroup" => {
+                        group = Some(attribute(start, "id")?.unwrap_or_default());
+                    }
+                    b"spectrum" => spectrum = Some(SpectrumBuilder::default()),
+                    b"binaryDataArray" => {
+                        if let Some(spectrum) = &mut spectrum {
+                            spectrum.arrays.push(Default::default());
+                        }
+                    }
+                    b"cvParam" => {
+                        let param = CvParam::new(start)?;
+                        if parent == Some("referenceableParamGroup") {
+                            if let Some(group) = &group {
+                                groups.entry(group.clone()).or_default().push(param);
+                            }
+                        } else if let Some(spectrum) = &mut spectrum {
+                            spectrum.param(parent, &param);
+                        }
+                    }
+                    b"referenceableParamGroupRef" => {
+                        if let Some(spectrum) = &mut spectrum {
+                            let id = attribute(start, "ref")?.unwrap_or_default();
+                            for param in groups.get(&id).into_iter().flatten() {
+                                spectrum.param(parent, param);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                if let Event::Start(start) = &event {
+                    path.push(String::from_utf8_lossy(start.local_name().as_ref()).into_owned());
+                }
+            }
+            Event::Text(text) => {
+                if path.last().map(String::as_str) == Some("binary") {
+                    if let Some(array) = spectrum
+                        .as_mut()
+                        .and_then(|spectrum| spectrum.arrays.last_mut())
+                    {
+                        array.text.push_str(&text.unescape()?);
+                    }
+                }
+            }
+            Event::End(end) => {
+                path.pop();
+                match end.local_name().as_ref() {
+                    b"referenceableParamGroup" => group = None,
+                    b"spectrum" => {
+                        if let Some(spectrum) = spectrum.take() {
+                            if spectrum.ms_level == 1 {
+                                spectra.push(spectrum.build()?);
+                            } else {
+                                skipped += 1;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    info!(spectra = spectra.len(), skipped, "mzML");
+    Ok(data_frame(&spectra)?)
+}
+
+/// mzML writer options
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) struct Options {
+    pub(crate) mass_to_charge: Bits,
+    pub(crate) intensity: Bits,
+    pub(crate) zlib: bool,
+    /// `indexedmzML` wrapper with spectrum offsets and checksum
+    pub(crate) index: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            mass_to_charge: Bits::X64,
+            intensity: Bits::X32,
+            zlib: true,
+            index: true,
+        }
+    }
+}
+
+/// Binary data array float size
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub(crate) enum Bits {
+    X32,
+    X64,
+}
+
+impl Bits {
+    pub(crate) const fn text(self) -> &'static str {
+        match self {
+            Self::X32 => "32-bit",
+            Self::X64 => "64-bit",
+        }
+    }
+
+    const fn accession(self) -> (&'static str, &'static str) {
+        match self {
+            Self::X32 => (FLOAT32, "32-bit float"),
+            Self::X64 => (FLOAT64, "64-bit float"),
+        }
+    }
+
+    /// Little endian bytes
+    fn bytes(self, values: &[f32]) -> Vec<u8> {
+        match self {
+            Self::X32 => values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+            Self::X64 => values
+                .iter()
+                .flat_map(|value| f64::from(*value).to_le_bytes())
+                .collect(),
+        }
+    }
+}
+
+/// Writes MS1 spectra as mzML, optionally wrapped in `indexedmzML`.
+pub(crate) fn write(data: &Data, options: Options) -> Result<Vec<u8>> {
+    let spectra = spectra(&data.data_frame)?;
+    let library = is_library(&data.data_frame);
+    let name = data.name();
+    let mut text = String::new();
+    writeln!(text, r#"<?xml version="1.0" encoding="utf-8"?>"#)?;
+    // The schema location is on the root element only
+    if options.index {
+        writeln!(
+            text,
+            r#"<indexedmzML xmlns="http://psi.hupo.org/ms/mzml" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://psi.hupo.org/ms/mzml {INDEXED_SCHEMA}">"#,
+        )?;
+        writeln!(
+            text,
+            r#"<mzML xmlns="http://psi.hupo.org/ms/mzml" id="{}" version="1.1.0">"#,
+            escape(&name),
+        )?;
+    } else {
+        writeln!(
+            text,
+            r#"<mzML xmlns="http://psi.hupo.org/ms/mzml" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance" xsi:schemaLocation="http://psi.hupo.org/ms/mzml {SCHEMA}" id="{}" version="1.1.0">"#,
+            escape(&name),
+        )?;
+    }
+    writeln!(text, r#"<cvList count="2">"#)?;
+    writeln!(
+        text,
+        r#"<cv id="MS" fullName="Proteomics Standards Initiative Mass Spectrometry Ontology" URI="https://raw.githubusercontent.com/HUPO-PSI/psi-ms-CV/master/psi-ms.obo"/>"#,
+    )?;
+    writeln!(
+        text,
+        r#"<cv id="UO" fullName="Unit Ontology" URI="https://raw.githubusercontent.com/bio-ontology-research-group/unit-ontology/master/unit.obo"/>"#,
+    )?;
+    writeln!(text, "</cvList>")?;
+    // File description
+    writeln!(text, "<fileDescription>")?;
+    writeln!(text, "<fileContent>")?;
+    cv_param(&mut text, MS1_SPECTRUM, "MS1 spectrum", "")?;
+    writeln!(text, "</fileContent>")?;
+    if let Some(file_name) = data.metadata.get("FileName") {
+        writeln!(text, r#"<sourceFileList count="1">"#)?;
+        writeln!(
+            text,
+            r#"<sourceFile id="source" name="{}" location="file:///">"#,
+            escape(file_name),
+        )?;
+        let (accession, name) = match Format::detect(file_name, &[]) {
+            Ok(Format::MzMl) => (MZML_FORMAT, "mzML format"),
+            Ok(Format::MzXml) => (ISB_MZXML_FORMAT, "ISB mzXML format"),
+            Ok(Format::Mgf) => (MASCOT_MGF_FORMAT, "Mascot MGF format"),
+            _ => (
+                MASS_SPECTROMETER_FILE_FORMAT,
+                "mass spectrometer file format",
+            ),
+        };
+        cv_param(&mut text, accession, name, "")?;
+        cv_param(
+            &mut text,
+            SCAN_NUMBER_ONLY_NATIVE_ID_FORMAT,
+            "scan number only nativeID format",
+            "",
+        )?;
+        writeln!(text, "</sourceFile>")?;
+        writeln!(text, "</sourceFileList>")?;
+    }
+    writeln!(text, "</fileDescription>")?;
+    // Software, instrument and data processing
+    writeln!(text, r#"<softwareList count="1">"#)?;
+    writeln!(
+        text,
+        r#"<software id="ms_viewer" version="{}">"#,
+        env!("CARGO_PKG_VERSION"),
+    )?;
+    cv_param(
+        &mut text,
+        CUSTOM_UNRELEASED_SOFTWARE_TOOL,
+        "custom unreleased software tool",
+        "MS viewer",
+    )?;
+    writeln!(text, "</software>")?;
+    writeln!(text, "</softwareList>")?;
+    writeln!(text, r#"<instrumentConfigurationList count="1">"#)?;
+    writeln!(text, r#"<instrumentConfiguration id="instrument"/>"#)?;
+    writeln!(text, "</instrumentConfigurationList>")?;
+    writeln!(text, r#"<dataProcessingList count="1">"#)?;
+    writeln!(text, r#"<dataProcessing id="ms_viewer_processing">"#)?;
+    writeln!(
+        text,
+        r#"<processingMethod order="0" softwareRef="ms_viewer">"#
+    )?;
+    cv_param(&mut text, CONVERSION_TO_MZML, "Conversion to mzML", "")?;
+    writeln!(text, "</processingMethod>")?;
+    writeln!(text, "</dataProcessing>")?;
+    writeln!(text, "</dataProcessingList>")?;
+    // Run
+    writeln!(
+        text,
+        r#"<run id="run" defaultInstrumentConfigurationRef="instrument">"#,
+    )?;
+    writeln!(
+        text,
+        r#"<spectrumList count="{}" defaultDataProcessingRef="ms_viewer_processing">"#,
+        spectra.len(),
+    )?;
+    let mut offsets = Vec::with_capacity(spectra.len());
+    for (index, spectrum) in spectra.iter().enumerate() {
+        let id = format!("scan={}", index + 1);
+        offsets.push((text.len(), id.clone()));
+        self::spectrum(&mut text, index, &id, spectrum, !library, options)?;
+    }
+    writeln!(text, "</spectrumList>")?;
+    writeln!(text, "</run>")?;
+    writeln!(text, "</mzML>")?;
+    if options.index {
+        let offset = text.len();
+        writeln!(text, r#"<indexList count="1">"#)?;
+        writeln!(text, r#"<index name="spectrum">"#)?;
+        for (offset, id) in offsets {
+            writeln!(text, r#"<offset idRef="{id}">{offset}</offset>"#)?;
+        }
+        writeln!(text, "</index>")?;
+        writeln!(text, "</indexList>")?;
+        writeln!(text, "<indexListOffset>{offset}</indexListOffset>")?;
+        // SHA-1 of the document up to and including `<fileChecksum>`
+        text.push_str("<fileChecksum>");
+        let checksum = Sha1::digest(text.as_bytes());
+        writeln!(text, "{checksum:x}</fileChecksum>")?;
+        writeln!(text, "</indexedmzML>")?;
+    }
+    info!(spectra = spectra.len(), "mzML");
+    Ok(text.into_bytes())
+}
+
+fn spectrum(
+    text: &mut String,
+    index: usize,
+    id: &str,
+    spectrum: &Spectrum,
+    retention_time: bool,
+    options: Options,
+) -> Result<()> {
+    writeln!(
+        text,
+        r#"<spectrum index="{index}" id="{id}" defaultArrayLength="{}">"#,
+        spectrum.signal.len(),
+    )?;
+    cv_param(text, MS_LEVEL, "ms level", "1")?;
+    cv_param(text, MS1_SPECTRUM, "MS1 spectrum", "")?;
+    let total = spectrum
+        .signal
+        .iter()
+        .map(|&signal| signal as f64)
+        .sum::<f64>();
+    cv_param(text, TOTAL_ION_CURRENT, "total ion current", total)?;
+    if let Some((mass_to_charge, signal)) = spectrum
+        .mass_to_charge
+        .iter()
+        .zip(&spectrum.signal)
+        .max_by(|(_, left), (_, right)| left.total_cmp(right))
+    {
+        cv_param(
+            text,
+            BASE_PEAK_MASS_TO_CHARGE,
+            "base peak m/z",
+            mass_to_charge,
+        )?;
+        cv_param(text, BASE_PEAK_INTENSITY, "base peak intensity", signal)?;
+    }
+    let (min, max) = spectrum.mass_to_charge.iter().fold(
+        (f32::INFINITY, f32::NEG_INFINITY),
+        |(min, max), &mass_to_charge| (min.min(mass_to_charge), max.max(mass_to_charge)),
+    );
+    if min <= max {
+        cv_param(
+            text,
+            LOWEST_OBSERVED_MASS_TO_CHARGE,
+            "lowest observed m/z",
+            min,
+        )?;
+        cv_param(
+            text,
+            HIGHEST_OBSERVED_MASS_TO_CHARGE,
+            "highest observed m/z",
+            max,
+        )?;
+    }
+    writeln!(text, r#"<scanList count="1">"#)?;
+    cv_param(text, NO_COMBINATION, "no combination", "")?;
+    writeln!(text, "<scan>")?;
+    if retention_time {
+        let retention_time = Time::new::<millisecond>(spectrum.retention_time);
+        writeln!(
+            text,
+            r#"<cvParam cvRef="MS" accession="{SCAN_START_TIME}" name="scan start time" value="{}" unitCvRef="UO" unitAccession="{SECOND}" unitName="second"/>"#,
+            retention_time.get::<second>(),
+        )?;
+    }
+    writeln!(text, "</scan>")?;
+    writeln!(text, "</scanList>")?;
+    writeln!(text, r#"<binaryDataArrayList count="2">"#)?;
+    binary_data_array(
+        text,
+        &spectrum.mass_to_charge,
+        options.mass_to_charge,
+        options.zlib,
+        (MASS_TO_CHARGE_ARRAY, "m/z array"),
+        (MASS_TO_CHARGE, "m/z"),
+    )?;
+    binary_data_array(
+        text,
+        &spectrum.signal,
+        options.intensity,
+        options.zlib,
+        (INTENSITY_ARRAY, "intensity array"),
+        (NUMBER_OF_DETECTOR_COUNTS, "number of detector counts"),
+    )?;
+    writeln!(text, "</binaryDataArrayList>")?;
+    writeln!(text, "</spectrum>")?;
+    Ok(())
+}
+
+fn binary_data_array(
+    text: &mut String,
+    values: &[f32],
+    bits: Bits,
+    zlib: bool,
+    (accession, name): (&str, &str),
+    (unit_accession, unit_name): (&str, &str),
+) -> Result<()> {
+    let mut bytes = bits.bytes(values);
+    if zlib {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(&bytes)?;
+        bytes = encoder.finish()?;
+    }
+    let encoded = BASE64_STANDARD.encode(bytes);
+    writeln!(
+        text,
+        r#"<binaryDataArray encodedLength="{}">"#,
+        encoded.len(),
+    )?;
+    let (precision, precision_name) = bits.accession();
+    cv_param(text, precision, precision_name, "")?;
+    if zlib {
+        cv_param(text, ZLIB_COMPRESSION, "zlib compression", "")?;
+    } else {
+        cv_param(text, NO_COMPRESSION, "no compression", "")?;
+    }
+    writeln!(
+        text,
+        r#"<cvParam cvRef="MS" accession="{accession}" name="{name}" value="" unitCvRef="MS" unitAccession="{unit_accession}" unitName="{unit_name}"/>"#,
+    )?;
+    writeln!(text, "<binary>{encoded}</binary>")?;
+    writeln!(text, "</binaryDataArray>")?;
+    Ok(())
+}
+
+fn cv_param(text: &mut String, accession: &str, name: &str, value: impl Display) -> Result<()> {
+    writeln!(
+        text,
+        r#"<cvParam cvRef="MS" accession="{accession}" name="{name}" value="{value}"/>"#,
+    )?;
+    Ok(())
+}
+
+/// Controlled vocabulary parameter
+#[derive(Clone, Debug, Default)]
+struct CvParam {
+    accession: String,
+    value: String,
+    unit_accession: Option<String>,
+}
+
+impl CvParam {
+    fn new(start: &BytesStart) -> Result<Self> {
+        Ok(Self {
+            accession: attribute(start, "accession")?.unwrap_or_default(),
+            value: attribute(start, "value")?.unwrap_or_default(),
+            unit_accession: attribute(start, "unitAccession")?,
+        })
+    }
+}
+
+/// Spectrum builder
+#[derive(Clone, Debug)]
+struct SpectrumBuilder {
+    ms_level: u8,
+    retention_time: Time,
+    arrays: Vec<BinaryDataArray>,
+}
+
+impl Default for SpectrumBuilder {
+    fn default() -> Self {
+        Self {
+            ms_level: 1,
+            retention_time: Time::new::<second>(0.0),
+            arrays: Vec::new(),
+        }
+    }
+}
+
+impl SpectrumBuilder {
+    fn param(&mut self, parent: Option<&str>, param: &CvParam) {
+        match (parent, param.accession.as_str()) {
+            (Some("spectrum"), MS_LEVEL) => {
+                self.ms_level = param.value.parse().unwrap_or(1);
+            }
+            (Some("scan"), SCAN_START_TIME) => {
+                let Ok(value) = param.value.parse() else {
+                    warn!(?param, "invalid scan start time");
+                    return;
+                };
+                self.retention_time = match param.unit_accession.as_deref() {
+                    Some(MILLISECOND) => Time::new::<millisecond>(value),
+                    Some(MINUTE) => Time::new::<minute>(value),
+                    Some(SECOND) | None => Time::new::<second>(value),
+                    Some(unit) => {
+                        warn!(unit, "unknown scan start time unit, seconds assumed");
+                        Time::new::<second>(value)
+                    }
+                };
+            }
+            (Some("binaryDataArray"), accession) => {
+                if let Some(array) = self.arrays.last_mut() {
+                    array.param(accession);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn build(self) -> Result<Spectrum> {
+        let mut spectrum = Spectrum {
+            retention_time: self.retention_time.get::<millisecond>(),
+            ..Default::default()
+        };
+        for array in self.arrays {
+            match array.kind {
+                Some(Kind::MassToCharge) => spectrum.mass_to_charge = array.decode()?,
+                Some(Kind::Intensity) => spectrum.signal = array.decode()?,
+                None => {}
+            }
+        }
+        ensure!(
+            spectrum.mass_to_charge.len() == spectrum.signal.len(),
+            "mass to charge and intensity arrays have different lengths ({} != {})",
+            spectrum.mass_to_charge.len(),
+            spectrum.signal.len(),
+        );
+        Ok(spectrum)
+    }
+}
+
+/// Binary data array
+#[derive(Clone, Debug, Default)]
+struct BinaryDataArray {
+    kind: Option<Kind>,
+    precision: Precision,
+    compression: Compression,
+    text: String,
+}
+
+impl BinaryDataArray {
+    fn param(&mut self, accession: &str) {
+        match accession {
+            MASS_TO_CHARGE_ARRAY => self.kind = Some(Kind::MassToCharge),
+            INTENSITY_ARRAY => self.kind = Some(Kind::Intensity),
+            INTEGER32 => self.precision = Precision::Integer32,
+            FLOAT32 => self.precision = Precision::Float32,
+            INTEGER64 => self.precision = Precision::Integer64,
+            FLOAT64 => self.precision = Precision::Float64,
+            ZLIB_COMPRESSION => self.compression = Compression::Zlib,
+            NO_COMPRESSION => self.compression = Compression::None,
+            accession if NUMPRESS.contains(&accession) => {
+                self.compression = Compression::Numpress;
+            }
+            _ => {}
+        }
+    }
+
+    fn decode(self) -> Result<Vec<f32>> {
+        let bytes = match self.compression {
+            Compression::None => decode(&self.text, false)?,
+            Compression::Zlib => decode(&self.text, true)?,
+            Compression::Numpress => bail!("numpress compression is not supported"),
+        };
+        let size = self.precision.size();
+        ensure!(
+            bytes.len() % size == 0,
+            "binary data array length {} is not a multiple of {size}",
+            bytes.len(),
+        );
+        let chunks = bytes.chunks_exact(size);
+        Ok(match self.precision {
+            Precision::Integer32 => chunks
+                .map(|chunk| i32::from_le_bytes(chunk.try_into().unwrap()) as _)
+                .collect(),
+            Precision::Float32 => chunks
+                .map(|chunk| f32::from_le_bytes(chunk.try_into().unwrap()))
+                .collect(),
+            Precision::Integer64 => chunks
+                .map(|chunk| i64::from_le_bytes(chunk.try_into().unwrap()) as _)
+                .collect(),
+            Precision::Float64 => chunks
+                .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()) as _)
+                .collect(),
+        })
+    }
+}
+
+/// Binary data array kind
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    MassToCharge,
+    Intensity,
+}
+
+/// Binary data array precision
+#[derive(Clone, Copy, Debug, Default)]
+enum Precision {
+    Integer32,
+    Float32,
+    Integer64,
+    #[default]
+    Float64,
+}
+
+impl Precision {
+    const fn size(self) -> usize {
+        match self {
+            Self::Integer32 | Self::Float32 => 4,
+            Self::Integer64 | Self::Float64 => 8,
+        }
+    }
+}
+
+/// Binary data array compression
+#[derive(Clone, Copy, Debug, Default)]
+enum Compression {
+    #[default]
+    None,
+    Zlib,
+    Numpress,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// MS1 (64-bit m/z from a referenceable group, minutes), MS2 and zlib
+    /// compressed MS1 spectra
+    const MZML: &str = r#"<indexedmzML><mzML><referenceableParamGroupList count="1"><referenceableParamGroup id="mz"><cvParam accession="MS:1000514" name="m/z array"/><cvParam accession="MS:1000523" name="64-bit float"/></referenceableParamGroup></referenceableParamGroupList>
+<run id="r"><spectrumList count="3">
+<spectrum index="0" id="scan=1" defaultArrayLength="3">
+ <cvParam cvRef="MS" accession="MS:1000511" name="ms level" value="1"/>
+ <scanList count="1"><scan><cvParam cvRef="MS" accession="MS:1000016" name="scan start time" value="0.5" unitCvRef="UO" unitAccession="UO:0000031" unitName="minute"/></scan></scanList>
+ <binaryDataArrayList count="2">
+  <binaryDataArray encodedLength="0"><referenceableParamGroupRef ref="mz"/><cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>
+   <binary>AAAAAAAASUAzMzMzM0NSQDMzMzMzY2JA</binary></binaryDataArray>
+  <binaryDataArray encodedLength="0"><cvParam cvRef="MS" accession="MS:1000515" name="intensity array"/><cvParam cvRef="MS" accession="MS:1000521" name="32-bit float"/>
+   <binary>AAAgQQAA+kQAAPpD</binary></binaryDataArray>
+ </binaryDataArrayList></spectrum>
+<spectrum index="1" id="scan=2" defaultArrayLength="1">
+ <cvParam cvRef="MS" accession="MS:1000511" name="ms level" value="2"/>
+ <scanList count="1"><scan><cvParam cvRef="MS" accession="MS:1000016" name="scan start time" value="0.51" unitCvRef="UO" unitAccession="UO:0000031" unitName="minute"/></scan></scanList>
+ <binaryDataArrayList count="2">
+  <binaryDataArray encodedLength="0"><referenceableParamGroupRef ref="mz"/><cvParam cvRef="MS" accession="MS:1000576" name="no compression" value=""/>
+   <binary>AAAAAAAATkA=</binary></binaryDataArray>
+  <binaryDataArray encodedLength="0"><cvParam cvRef="MS" accession="MS:1000515" name="intensity array"/><cvParam cvRef="MS" accession="MS:1000521" name="32-bit float"/>
+   <binary>AACAPw==</binary></binaryDataArray>
+ </binaryDataArrayList></spectrum>
+<spectrum index="2" id="scan=3" defaultArrayLength="2">
+ <cvParam cvRef="MS" accession="MS:1000511" name="ms level" value="1"/>
+ <scanList count="1"><scan><cvParam cvRef="MS" accession="MS:1000016" name="scan start time" value="0.52" unitCvRef="UO" unitAccession="UO:0000031" unitName="minute"/></scan></scanList>
+ <binaryDataArrayList count="2">
+  <binaryDataArray encodedLength="0"><referenceableParamGroupRef ref="mz"/><cvParam cvRef="MS" accession="MS:1000574" name="zlib compression" value=""/>
+   <binary>eJxjYACCBk8HBjAd5AAADQ4CHA==</binary></binaryDataArray>
+  <binaryDataArray encodedLength="0"><cvParam cvRef="MS" accession="MS:1000515" name="intensity array"/><cvParam cvRef="MS" accession="MS:1000521" name="32-bit float"/><cvParam accession="MS:1000574"/>
+   <binary>eJxjCDjszsDg4AAACO0B2w==</binary></binaryDataArray>
+ </binaryDataArrayList></spectrum>
+</spectrumList></run></mzML></indexedmzML>"#;
+    #[test]
+    fn read() -> Result<()> {
+        let data_frame = super::read(MZML.as_bytes())?;
+        assert_eq!(
+            spectra(&data_frame)?,
+            [
+                Spectrum {
+                    retention_time: 30000.0,
+                    mass_to_charge: vec![50.0, 73.05, 147.1],
+                    signal: vec![10.0, 2000.0, 500.0],
+                },
+                Spectrum {
+                    retention_time: 31200.0,
+                    mass_to_charge: vec![51.0, 74.0],
+                    signal: vec![100000.0, 3.0],
+                },
+            ],
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip() -> Result<()> {
+        let expected = [
+            Spectrum {
+                retention_time: 61500.0,
+                mass_to_charge: vec![50.5, 73.0, 147.1],
+                signal: vec![10.0, 2000.0, 0.25],
+            },
+            Spectrum {
+                retention_time: 63250.0,
+                ..Default::default()
+            },
+        ];
+        let data = Data {
+            data_frame: data_frame(&expected)?,
+            metadata: [("FileName".to_owned(), "run.D".to_owned())].into(),
+        };
+        for options in [
+            Default::default(),
+            Options {
+                mass_to_charge: Bits::X32,
+                intensity: Bits::X64,
+                zlib: false,
+                index: false,
+            },
+        ] {
+            let bytes = write(&data, options)?;
+            assert_eq!(spectra(&super::read(&bytes)?)?, expected, "{options:?}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn source_file() -> Result<()> {
+        let data = Data {
+            data_frame: data_frame(&[])?,
+            metadata: [("FileName".to_owned(), "run.mzXML".to_owned())].into(),
+        };
+        let text = String::from_utf8(write(&data, Default::default())?)?;
+        assert!(text.contains(r#"<sourceFile id="source" name="run.mzXML" location="file:///">"#));
+        assert!(text.contains(r#"accession="MS:1000566" name="ISB mzXML format""#));
+        assert!(text.contains(r#"accession="MS:1000776" name="scan number only nativeID format""#));
+        assert_eq!(text.matches("xsi:schemaLocation").count(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,250 @@
+use super::{attribute, data_frame, decode, Spectrum};
+use anyhow::{bail, ensure, Context, Result};
+use polars::frame::DataFrame;
+use quick_xml::{
+    events::{BytesStart, Event},
+    Reader,
+};
+use tracing::info;
+use uom::si::{
+    f64::Time,
+    time::{day, hour, millisecond, minute, second},
+};
+
+// http://tools.proteomecenter.org/wiki/index.php?title=Formats:mzXML
+
+/// Reads mzXML into a data frame with MS1 scans.
+pub(crate) fn read(bytes: &[u8]) -> Result<DataFrame> {
+    let mut reader = Reader::from_reader(bytes);
+    reader.config_mut().trim_text(true);
+    // Scans are nested: MSn scans are children of their precursor scan
+    let mut scans = Vec::new();
+    let mut peaks = None;
+    let mut spectra = Vec::new();
+    let mut skipped = 0;
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => match start.local_name().as_ref() {
+                b"scan" => scans.push(Scan::new(&start)?),
+                b"peaks" => peaks = Some(Peaks::new(&start)?),
+                _ => {}
+            },
+            Event::Empty(start) => match start.local_name().as_ref() {
+                b"scan" => {
+                    let scan = Scan::new(&start)?;
+                    if scan.ms_level == 1 {
+                        spectra.push(scan.build()?);
+                    } else {
+                        skipped += 1;
+                    }
+                }
+                b"peaks" => {
+                    if let Some(scan) = scans.last_mut() {
+                        scan.peaks = Peaks::new(&start)?;
+                    }
+                }
+                _ => {}
+            },
+            Event::Text(text) => {
+                if let Some(peaks) = &mut peaks {
+                    peaks.text.push_str(&text.unescape()?);
+                }
+            }
+            Event::End(end) => match end.local_name().as_ref() {
+                b"scan" => {
+                    if let Some(scan) = scans.pop() {
+                        if scan.ms_level == 1 {
+                            spectra.push(scan.build()?);
+                        } else {
+                            skipped += 1;
+                        }
+                    }
+                }
+                b"peaks" => {
+                    if let (Some(scan), Some(peaks)) = (scans.last_mut(), peaks.take()) {
+                        scan.peaks = peaks;
+                    }
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    info!(spectra = spectra.len(), skipped, "mzXML");
+    Ok(data_frame(&spectra)?)
+}
+
+/// Scan
+#[derive(Clone, Debug, Default)]
+struct Scan {
+    ms_level: u8,
+    retention_time: Option<Time>,
+    peaks: Peaks,
+}
+
+impl Scan {
+    fn new(start: &BytesStart) -> Result<Self> {
+        let ms_level = match attribute(start, "msLevel")? {
+            Some(ms_level) => ms_level
+                .parse()
+                .with_context(|| format!("invalid msLevel {ms_level:?}"))?,
+            None => 1,
+        };
+        let retention_time = match attribute(start, "retentionTime")? {
+            Some(retention_time) => Some(duration(&retention_time)?),
+            None => None,
+        };
+        Ok(Self {
+            ms_level,
+            retention_time,
+            peaks: Default::default(),
+        })
+    }
+
+    fn build(self) -> Result<Spectrum> {
+        let values = self.peaks.decode()?;
+        Ok(Spectrum {
+            retention_time: self
+                .retention_time
+                .map_or(0.0, |retention_time| retention_time.get::<millisecond>()),
+            mass_to_charge: values.iter().step_by(2).copied().collect(),
+            signal: values.iter().skip(1).step_by(2).copied().collect(),
+        })
+    }
+}
+
+/// Peaks (m/z-intensity pairs)
+#[derive(Clone, Debug)]
+struct Peaks {
+    precision: u8,
+    zlib: bool,
+    text: String,
+}
+
+impl Default for Peaks {
+    fn default() -> Self {
+        Self {
+            precision: 32,
+            zlib: false,
+            text: String::new(),
+        }
+    }
+}
+
+impl Peaks {
+    fn new(start: &BytesStart) -> Result<Self> {
+        if let Some(byte_order) = attribute(start, "byteOrder")? {
+            ensure!(
+                byte_order == "network",
+                "unsupported peaks byte order {byte_order:?}",
+            );
+        }
+        let precision = match attribute(start, "precision")?.as_deref() {
+            Some("32") | None => 32,
+            Some("64") => 64,
+            Some(precision) => bail!("unsupported peaks precision {precision:?}"),
+        };
+        let zlib = match attribute(start, "compressionType")?.as_deref() {
+            Some("zlib") => true,
+            Some("none") | None => false,
+            Some(compression) => bail!("unsupported peaks compression {compression:?}"),
+        };
+        Ok(Self {
+            precision,
+            zlib,
+            text: String::new(),
+        })
+    }
+
+    fn decode(&self) -> Result<Vec<f32>> {
+        let bytes = decode(&self.text, self.zlib)?;
+        let size = self.precision as usize / 8;
+        ensure!(
+            bytes.len() % (2 * size) == 0,
+            "peaks length {} is not a multiple of {}",
+            bytes.len(),
+            2 * size,
+        );
+        let chunks = bytes.chunks_exact(size);
+        Ok(if self.precision == 64 {
+            chunks
+                .map(|chunk| f64::from_be_bytes(chunk.try_into().unwrap()) as _)
+                .collect()
+        } else {
+            chunks
+                .map(|chunk| f32::from_be_bytes(chunk.try_into().unwrap()))
+                .collect()
+        })
+    }
+}
+
+/// Parses an `xs:duration` (`PT1.5S`, `PT2M3.25S`, `P1DT0H`)
+fn duration(text: &str) -> Result<Time> {
+    let invalid = || format!("invalid retention time {text:?}");
+    let rest = text.trim().strip_prefix('P').with_context(invalid)?;
+    let mut time = Time::new::<second>(0.0);
+    let mut designators = false;
+    let mut value = String::new();
+    let mut time_part = false;
+    for char in rest.chars() {
+        match char {
+            'T' => time_part = true,
+            '0'..='9' | '.' => value.push(char),
+            _ => {
+                let number: f64 = value.parse().with_context(invalid)?;
+                value.clear();
+                designators = true;
+                time += match char {
+                    'D' if !time_part => Time::new::<day>(number),
+                    'H' if time_part => Time::new::<hour>(number),
+                    'M' if time_part => Time::new::<minute>(number),
+                    'S' if time_part => Time::new::<second>(number),
+                    _ => bail!(invalid()),
+                };
+            }
+        }
+    }
+    ensure!(designators && value.is_empty(), invalid());
+    Ok(time)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::formats::spectra;
+
+    /// MS1 scan with a nested MS2 scan, zlib compressed 64-bit MS1 scan and
+    /// empty MS1 scan
+    const MZXML: &str = r#"<mzXML><msRun scanCount="3">
+<scan num="1" msLevel="1" peaksCount="2" retentionTime="PT1M1.5S"><peaks precision="32" byteOrder="network" pairOrder="m/z-int">QkgAAEEgAABCkgAAQ0gAAA==</peaks>
+ <scan num="2" msLevel="2" retentionTime="PT62S"><peaks precision="32" byteOrder="network">P4AAAD+AAAA=</peaks></scan>
+</scan>
+<scan num="3" msLevel="1" retentionTime="PT63.25S"><peaks precision="64" byteOrder="network" compressionType="zlib" contentType="m/z-int">eJxz8DzAAAKOei0NIBoAG7kCvQ==</peaks></scan>
+<scan num="4" msLevel="1" retentionTime="PT64S" peaksCount="0"><peaks precision="32" byteOrder="network"/></scan>
+</msRun></mzXML>"#;
+    #[test]
+    fn read() -> Result<()> {
+        let data_frame = super::read(MZXML.as_bytes())?;
+        assert_eq!(
+            spectra(&data_frame)?,
+            [
+                Spectrum {
+                    retention_time: 61500.0,
+                    mass_to_charge: vec![50.0, 73.0],
+                    signal: vec![10.0, 200.0],
+                },
+                Spectrum {
+                    retention_time: 63250.0,
+                    mass_to_charge: vec![51.5],
+                    signal: vec![1000000.0],
+                },
+                Spectrum {
+                    retention_time: 64000.0,
+                    ..Default::default()
+                },
+            ],
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,363 @@
+use anyhow::{bail, ensure, Context, Result};
+use std::fmt::{self, Display, Formatter};
+
+// https://docs.unidata.ucar.edu/netcdf-c/current/file_format_specifications.html
+
+const ABSENT: u32 = 0x00;
+const NC_DIMENSION: u32 = 0x0A;
+const NC_VARIABLE: u32 = 0x0B;
+const NC_ATTRIBUTE: u32 = 0x0C;
+const STREAMING: u32 = u32::MAX;
+
+/// NetCDF classic (CDF-1) and 64-bit offset (CDF-2) file
+#[derive(Clone, Debug)]
+pub(crate) struct NetCdf<'a> {
+    bytes: &'a [u8],
+    records: usize,
+    /// Dimension lengths (zero length is the record dimension)
+    dimensions: Vec<usize>,
+    pub(crate) attributes: Vec<Attribute>,
+    variables: Vec<Variable>,
+}
+
+impl<'a> NetCdf<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> Result<Self> {
+        let mut cursor = Cursor { bytes, position: 0 };
+        ensure!(cursor.take(3)? == b"CDF", "not a netCDF classic file");
+        let version = cursor.take(1)?[0];
+        ensure!(
+            version == 1 || version == 2,
+            "unsupported netCDF version {version}",
+        );
+        let records = cursor.u32()?;
+        // Dimensions
+        let dimensions = cursor.list(NC_DIMENSION, |cursor| {
+            cursor.name()?;
+            Ok(cursor.u32()? as _)
+        })?;
+        // Attributes
+        let attributes = cursor.attributes()?;
+        // Variables
+        let variables = cursor.list(NC_VARIABLE, |cursor| {
+            let name = cursor.name()?;
+            let count = cursor.u32()? as usize;
+            let dimension_ids = (0..count)
+                .map(|_| Ok(cursor.u32()? as usize))
+                .collect::<Result<Vec<_>>>()?;
+            let attributes = cursor.attributes()?;
+            let r#type = Type::new(cursor.u32()?)?;
+            let size = cursor.u32()? as _;
+            let begin = if version == 1 {
+                cursor.u32()? as _
+            } else {
+                cursor.u64()? as _
+            };
+            Ok(Variable {
+                name,
+                dimension_ids,
+                attributes,
+                r#type,
+                size,
+                begin,
+            })
+        })?;
+        for variable in &variables {
+            for &id in &variable.dimension_ids {
+                ensure!(
+                    id < dimensions.len(),
+                    "variable {} has invalid dimension id {id}",
+                    variable.name,
+                );
+            }
+        }
+        let mut net_cdf = Self {
+            bytes,
+            records: records as _,
+            dimensions,
+            attributes,
+            variables,
+        };
+        if records == STREAMING {
+            net_cdf.records = net_cdf.streaming_records()?;
+        }
+        Ok(net_cdf)
+    }
+
+    /// Variable attribute
+    pub(crate) fn variable_attribute(&self, variable: &str, name: &str) -> Option<&Value> {
+        self.variables
+            .iter()
+            .find(|candidate| candidate.name == variable)?
+            .attributes
+            .iter()
+            .find(|attribute| attribute.name == name)
+            .map(|attribute| &attribute.value)
+    }
+
+    /// Variable values converted to `f64`
+    pub(crate) fn variable(&self, name: &str) -> Result<Vec<f64>> {
+        let variable = self
+            .variables
+            .iter()
+            .find(|variable| variable.name == name)
+            .with_context(|| format!("variable {name} not found"))?;
+        ensure!(
+            variable.r#type != Type::Char,
+            "variable {name} is not numeric",
+        );
+        if self.is_record(variable) {
+            let count = self.count(&variable.dimension_ids[1..])?;
+            let record_size = self.record_size()?;
+            let mut values = Vec::new();
+            for record in 0..self.records {
+                let begin = record
+                    .checked_mul(record_size)
+                    .and_then(|offset| variable.begin.checked_add(offset))
+                    .with_context(|| format!("variable {name} record {record} overflows"))?;
+                values.extend(self.values(variable, begin, count)?);
+            }
+            Ok(values)
+        } else {
+            self.values(
+                variable,
+                variable.begin,
+                self.count(&variable.dimension_ids)?,
+            )
+        }
+    }
+
+    /// Number of values spanned by the dimensions
+    fn count(&self, dimension_ids: &[usize]) -> Result<usize> {
+        dimension_ids
+            .iter()
+            .try_fold(1_usize, |count, &id| count.checked_mul(self.dimensions[id]))
+            .context("netCDF dimensions overflow")
+    }
+
+    fn values(&self, variable: &Variable, begin: usize, count: usize) -> Result<Vec<f64>> {
+        let size = variable.r#type.size();
+        // Offsets and lengths come from the header
+        let end = count
+            .checked_mul(size)
+            .and_then(|length| begin.checked_add(length))
+            .with_context(|| format!("variable {} size overflows", variable.name))?;
+        ensure!(
+            end <= self.bytes.len(),
+            "variable {} is out of bounds",
+            variable.name,
+        );
+        Ok(self.bytes[begin..end]
+            .chunks_exact(size)
+            .map(|chunk| variable.r#type.number(chunk))
+            .collect())
+    }
+
+    fn is_record(&self, variable: &Variable) -> bool {
+        variable
+            .dimension_ids
+            .first()
+            .is_some_and(|&id| self.dimensions[id] == 0)
+    }
+
+    fn record_size(&self) -> Result<usize> {
+        let mut records = self
+            .variables
+            .iter()
+            .filter(|variable| self.is_record(variable));
+        let record_size = match (records.next(), records.next()) {
+            // A single record variable is not padded
+            (Some(variable), None) => self
+                .count(&variable.dimension_ids[1..])?
+                .checked_mul(variable.r#type.size()),
+            _ => self
+                .variables
+                .iter()
+                .filter(|variable| self.is_record(variable))
+                .try_fold(0_usize, |size, variable| size.checked_add(variable.size)),
+        };
+        record_size.context("netCDF record size overflows")
+    }
+
+    fn streaming_records(&self) -> Result<usize> {
+        let record_size = self.record_size()?;
+        let begin = self
+            .variables
+            .iter()
+            .filter(|variable| self.is_record(variable))
+            .map(|variable| variable.begin)
+            .min();
+        Ok(match begin {
+            Some(begin) if record_size != 0 => self.bytes.len().saturating_sub(begin) / record_size,
+            _ => 0,
+        })
+    }
+}
+
+/// Attribute
+#[derive(Clone, Debug)]
+pub(crate) struct Attribute {
+    pub(crate) name: String,
+    pub(crate) value: Value,
+}
+
+/// Attribute value
+#[derive(Clone, Debug)]
+pub(crate) enum Value {
+    Text(String),
+    Numbers(Vec<f64>),
+}
+
+impl Value {
+    pub(crate) fn number(&self) -> Option<f64> {
+        match self {
+            Self::Text(text) => text.trim().parse().ok(),
+            Self::Numbers(numbers) => numbers.first().copied(),
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Text(text) => f.write_str(text),
+            Self::Numbers(numbers) => {
+                for (index, number) in numbers.iter().enumerate() {
+                    if index != 0 {
+                        f.write_str(", ")?;
+                    }
+                    Display::fmt(number, f)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Variable
+#[derive(Clone, Debug)]
+struct Variable {
+    name: String,
+    dimension_ids: Vec<usize>,
+    attributes: Vec<Attribute>,
+    r#type: Type,
+    size: usize,
+    begin: usize,
+}
+
+/// External data type
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Type {
+    Byte,
+    Char,
+    Short,
+    Int,
+    Float,
+    Double,
+}
+
+impl Type {
+    fn new(value: u32) -> Result<Self> {
+        Ok(match value {
+            1 => Self::Byte,
+            2 => Self::Char,
+            3 => Self::Short,
+            4 => Self::Int,
+            5 => Self::Float,
+            6 => Self::Double,
+            _ => bail!("unsupported netCDF type {value}"),
+        })
+    }
+
+    const fn size(self) -> usize {
+        match self {
+            Self::Byte | Self::Char => 1,
+            Self::Short => 2,
+            Self::Int | Self::Float => 4,
+            Self::Double => 8,
+        }
+    }
+
+    fn number(self, bytes: &[u8]) -> f64 {
+        match self {
+            Self::Byte => bytes[0] as i8 as _,
+            Self::Char => bytes[0] as _,
+            Self::Short => i16::from_be_bytes([bytes[0], bytes[1]]) as _,
+            Self::Int => i32::from_be_bytes(bytes.try_into().unwrap()) as _,
+            Self::Float => f32::from_be_bytes(bytes.try_into().unwrap()) as _,
+            Self::Double => f64::from_be_bytes(bytes.try_into().unwrap()),
+        }
+    }
+}
+
+/// Header cursor
+struct Cursor<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|&end| end <= self.bytes.len())
+            .context("unexpected end of netCDF header")?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// Takes `count` bytes, skipping the padding to a 4 byte boundary
+    fn padded(&mut self, count: usize) -> Result<&'a [u8]> {
+        let bytes = self.take(count)?;
+        self.take((4 - count % 4) % 4)?;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let count = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.padded(count)?).into_owned())
+    }
+
+    fn list<T>(&mut self, tag: u32, mut f: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let actual = self.u32()?;
+        let count = self.u32()?;
+        match actual {
+            ABSENT if count == 0 => Ok(Vec::new()),
+            _ if actual == tag => (0..count).map(|_| f(self)).collect(),
+            _ => bail!("unexpected netCDF header tag {actual:#x}, expected {tag:#x}"),
+        }
+    }
+
+    fn attributes(&mut self) -> Result<Vec<Attribute>> {
+        self.list(NC_ATTRIBUTE, |cursor| {
+            let name = cursor.name()?;
+            let r#type = Type::new(cursor.u32()?)?;
+            let count = cursor.u32()? as usize;
+            let length = count
+                .checked_mul(r#type.size())
+                .context("netCDF attribute size overflows")?;
+            let bytes = cursor.padded(length)?;
+            let value = if r#type == Type::Char {
+                let text = String::from_utf8_lossy(bytes);
+                Value::Text(text.trim_end_matches('\0').to_owned())
+            } else {
+                Value::Numbers(
+                    bytes
+                        .chunks_exact(r#type.size())
+                        .map(|chunk| r#type.number(chunk))
+                        .collect(),
+                )
+            };
+            Ok(Attribute { name, value })
+        })
+    }
+}
@@ -0,0 +1,314 @@
+use crate::app::data::Data;
+use anyhow::{anyhow, bail, Result};
+use itertools::Itertools;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::Cursor;
+use std::{
+    fmt::{self, Display, Formatter},
+    path::Path,
+    str,
+};
+
+/// Bytes searched for text format signatures
+const HEAD: usize = 4096;
+
+/// Data format
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(crate) enum Format {
+    Andi,
+    Bin,
+    ChemStation,
+    Csv,
+    Ipc,
+    Jcamp,
+    Mgf,
+    Msp,
+    MzMl,
+    MzXml,
+    Parquet,
+    Ron,
+}
+
+impl Format {
+    pub(crate) const ALL: [Self; 12] = [
+        Self::Andi,
+        Self::Bin,
+        Self::ChemStation,
+        Self::Csv,
+        Self::Ipc,
+        Self::Jcamp,
+        Self::Mgf,
+        Self::Msp,
+        Self::MzMl,
+        Self::MzXml,
+        Self::Parquet,
+        Self::Ron,
+    ];
+
+    pub(crate) const fn name(self) -> &'static str {
+        match self {
+            Self::Andi => "ANDI-MS",
+            Self::Bin => "Bincode",
+            Self::ChemStation => "Agilent ChemStation",
+            Self::Csv => "CSV/TSV",
+            Self::Ipc => "Arrow IPC",
+            Self::Jcamp => "JCAMP-DX",
+            Self::Mgf => "MGF",
+            Self::Msp => "MSP",
+            Self::MzMl => "mzML",
+            Self::MzXml => "mzXML",
+            Self::Parquet => "Parquet",
+            Self::Ron => "RON",
+        }
+    }
+
+    /// Lowercase file extensions, the first one is used for writing
+    pub(crate) const fn extensions(self) -> &'static [&'static str] {
+        match self {
+            Self::Andi => &["cdf"],
+            Self::Bin => &["bin"],
+            Self::ChemStation => &["ms"],
+            Self::Csv => &["csv", "tsv", "txt"],
+            Self::Ipc => &["arrow", "feather", "ipc"],
+            Self::Jcamp => &["jdx", "dx", "jcamp"],
+            Self::Mgf => &["mgf"],
+            Self::Msp => &["msp"],
+            Self::MzMl => &["mzml"],
+            Self::MzXml => &["mzxml"],
+            Self::Parquet => &["parquet"],
+            Self::Ron => &["ron"],
+        }
+    }
+
+    /// File signature: leading magic bytes or a text marker near the start
+    pub(crate) const fn magic(self) -> Magic {
+        match self {
+            Self::Andi => Magic::Prefix(&[b"CDF\x01", b"CDF\x02"]),
+            Self::ChemStation => Magic::Prefix(&[&[0x01, 0x32, 0x00, 0x00]]),
+            Self::Ipc => Magic::Prefix(&[b"ARROW1"]),
+            Self::Jcamp => Magic::Text(&["##TITLE"]),
+            Self::Mgf => Magic::Text(&["BEGIN IONS"]),
+            Self::Msp => Magic::Text(&["Name:", "NAME:"]),
+            Self::MzMl => Magic::Text(&["<mzML", "<indexedmzML"]),
+            Self::MzXml => Magic::Text(&["<mzXML"]),
+            Self::Parquet => Magic::Prefix(&[b"PAR1"]),
+            Self::Bin | Self::Csv | Self::Ron => Magic::None,
+        }
+    }
+
+    /// Capabilities on the current target (no Arrow IPC and Parquet on the
+    /// web)
+    pub(crate) const fn capabilities(self) -> Capabilities {
+        let (read, write, scope) = match self {
+            Self::Ipc | Self::Parquet if cfg!(target_arch = "wasm32") => (false, false, Scope::Run),
+            Self::Andi | Self::ChemStation | Self::Csv | Self::Msp | Self::MzXml => {
+                (true, false, Scope::Run)
+            }
+            Self::Bin | Self::Ipc | Self::Mgf | Self::MzMl | Self::Parquet | Self::Ron => {
+                (true, true, Scope::Run)
+            }
+            Self::Jcamp => (true, true, Scope::Spectrum),
+        };
+        Capabilities { read, write, scope }
+    }
+
+    /// Detects the format by file extension, then by signature.
+    pub(crate) fn detect(name: &str, bytes: &[u8]) -> Result<Self> {
+        let extension = Path::new(name)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+        if let Some(extension) = extension {
+            if let Some(format) = Self::ALL
+                .into_iter()
+                .find(|format| format.extensions().contains(&&*extension))
+            {
+                return Ok(format);
+            }
+        }
+        if let Some(format) = Self::ALL
+            .into_iter()
+            .find(|format| format.magic().matches(bytes))
+        {
+            return Ok(format);
+        }
+        bail!(
+            "unsupported file {name}, supported formats: {}",
+            Self::ALL
+                .into_iter()
+                .filter(|format| format.capabilities().read)
+                .map(|format| format!("{format} (.{})", format.extensions().join(", .")))
+                .join(", "),
+        )
+    }
+
+    /// Reads a run or library.
+    pub(crate) fn read(self, bytes: Vec<u8>) -> Result<Data> {
+        let (data_frame, metadata) = match self {
+            Self::Andi => super::andi(&bytes)?,
+            Self::Bin => (bincode::deserialize(&bytes)?, Default::default()),
+            Self::ChemStation => super::chemstation(&bytes)?,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Ipc => (
+                IpcReader::new(Cursor::new(bytes)).finish()?,
+                Default::default(),
+            ),
+            Self::Jcamp => super::jcamp(str::from_utf8(&bytes)?)?,
+            Self::Mgf => super::mgf(str::from_utf8(&bytes)?)?,
+            Self::Msp => (super::msp(str::from_utf8(&bytes)?)?, Default::default()),
+            Self::MzMl => (super::mzml(&bytes)?, Default::default()),
+            Self::MzXml => (super::mzxml(&bytes)?, Default::default()),
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Parquet => (
+                ParquetReader::new(Cursor::new(bytes)).finish()?,
+                Default::default(),
+            ),
+            #[cfg(target_arch = "wasm32")]
+            Self::Ipc | Self::Parquet => bail!("{self} is not available on the web"),
+            Self::Ron => (ron(&bytes)?, Default::default()),
+            Self::Csv => bail!("{self} needs a column mapping"),
+        };
+        Ok(Data {
+            data_frame,
+            metadata,
+        })
+    }
+
+    /// Writes a run or library (mzML with the default options).
+    pub(crate) fn write(self, data: &Data) -> Result<Vec<u8>> {
+        let data_frame = &data.data_frame;
+        Ok(match self {
+            Self::Bin => bincode::serialize(data_frame)?,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Ipc => {
+                let mut cursor = Cursor::new(Vec::new());
+                IpcWriter::new(&mut cursor).finish(&mut data_frame.clone())?;
+                cursor.into_inner()
+            }
+            Self::Mgf => super::to_mgf(data_frame)?.into_bytes(),
+            Self::MzMl => super::to_mzml(data, Default::default())?,
+            #[cfg(not(target_arch = "wasm32"))]
+            Self::Parquet => {
+                let mut cursor = Cursor::new(Vec::new());
+                ParquetWriter::new(&mut cursor).finish(&mut data_frame.clone())?;
+                cursor.into_inner()
+            }
+            Self::Ron => ron::ser::to_string_pretty(data_frame, Default::default())?.into_bytes(),
+            #[cfg(target_arch = "wasm32")]
+            Self::Ipc | Self::Parquet => bail!("{self} is not available on the web"),
+            _ => bail!("{self} can't write a run"),
+        })
+    }
+}
+
+/// Reads a RON data frame, errors point at the line and column.
+fn ron(bytes: &[u8]) -> Result<DataFrame> {
+    ron::de::from_bytes(bytes).map_err(|error| {
+        anyhow!(
+            "invalid RON at line {}, column {}: {}",
+            error.position.line,
+            error.position.col,
+            error.code,
+        )
+    })
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Format capabilities
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Capabilities {
+    pub(crate) read: bool,
+    pub(crate) write: bool,
+    pub(crate) scope: Scope,
+}
+
+/// What a file holds: a single mass spectrum or a full run
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Scope {
+    Spectrum,
+    Run,
+}
+
+/// File signature
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Magic {
+    None,
+    Prefix(&'static [&'static [u8]]),
+    Text(&'static [&'static str]),
+}
+
+impl Magic {
+    pub(crate) fn matches(self, bytes: &[u8]) -> bool {
+        match self {
+            Self::None => false,
+            Self::Prefix(prefixes) => prefixes.iter().any(|prefix| bytes.starts_with(prefix)),
+            Self::Text(markers) => {
+                let head = String::from_utf8_lossy(&bytes[..bytes.len().min(HEAD)]);
+                markers.iter().any(|marker| head.contains(marker))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::formats::peak;
+    use polars::chunked_array::builder::get_list_builder;
+
+    /// Run with a nested mass spectrum and nulls (scalar, list and peak)
+    fn data() -> Result<Data> {
+        let mut builder = get_list_builder(&peak(), 2, 2, "MassSpectrum")?;
+        let fields = [
+            Series::new("MassToCharge", [73.0_f32, 147.1]),
+            Series::new("Signal", [Some(100.0_f32), None]),
+        ];
+        builder.append_series(&StructChunked::from_series("", &fields)?.into_series())?;
+        builder.append_null();
+        let data_frame = DataFrame::new(vec![
+            Series::new("RetentionTime", [Some(1000), None]),
+            Series::new("Name", [Some("Benzene"), None]),
+            builder.finish().into_series(),
+        ])?;
+        Ok(Data {
+            data_frame,
+            metadata: Default::default(),
+        })
+    }
+
+    #[test]
+    fn bin() -> Result<()> {
+        let data = data()?;
+        let bytes = Format::Bin.write(&data)?;
+        let read = Format::Bin.read(bytes)?;
+        assert!(read.data_frame.equals_missing(&data.data_frame));
+        Ok(())
+    }
+
+    #[test]
+    fn ron() -> Result<()> {
+        let data = data()?;
+        let bytes = Format::Ron.write(&data)?;
+        let read = Format::Ron.read(bytes)?;
+        assert!(read.data_frame.equals_missing(&data.data_frame));
+        Ok(())
+    }
+
+    #[test]
+    fn ron_error() {
+        let error = Format::Ron
+            .read(b"(\n    columns: [,\n".to_vec())
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "invalid RON at line 2, column 15: Expected opening `{`",
+        );
+    }
+}
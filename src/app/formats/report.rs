@@ -0,0 +1,110 @@
+use super::figure::Figure;
+use crate::app::data::Metadata;
+use polars::prelude::*;
+use quick_xml::escape::escape;
+use std::fmt::Write;
+
+/// Figure size in the report
+const SIZE: [f64; 2] = [800.0, 400.0];
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #202020; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #d0d0d0; padding: 2px 8px; text-align: left; vertical-align: top; }
+th { background: #f0f0f0; }
+tr:nth-child(even) td { background: #fafafa; }
+svg { max-width: 100%; height: auto; }
+footer { margin-top: 2em; color: #808080; font-size: small; }";
+
+/// Analysis report (one sample)
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Report {
+    pub(crate) name: String,
+    pub(crate) metadata: Metadata,
+    /// Total ion chromatogram
+    pub(crate) tic: Option<Figure>,
+    /// Grouped by retention time table
+    pub(crate) table: DataFrame,
+    /// Selected mass spectra (title, figure)
+    pub(crate) spectra: Vec<(String, Figure)>,
+}
+
+impl Report {
+    /// Self-contained HTML document (inline style and SVG, no external
+    /// resources).
+    pub(crate) fn html(&self) -> PolarsResult<String> {
+        let mut html = String::new();
+        let name = escape(&self.name);
+        writeln!(html, "<!DOCTYPE html>").ok();
+        writeln!(html, r#"<html lang="en">"#).ok();
+        writeln!(html, "<head>").ok();
+        writeln!(html, r#"<meta charset="utf-8">"#).ok();
+        writeln!(html, "<title>{name}</title>").ok();
+        writeln!(html, "<style>\n{STYLE}\n</style>").ok();
+        writeln!(html, "</head>").ok();
+        writeln!(html, "<body>").ok();
+        writeln!(html, "<h1>{name}</h1>").ok();
+        // Metadata
+        if !self.metadata.is_empty() {
+            writeln!(html, "<h2>Metadata</h2>").ok();
+            writeln!(html, "<table>").ok();
+            for (key, value) in &self.metadata {
+                writeln!(
+                    html,
+                    "<tr><th>{}</th><td>{}</td></tr>",
+                    escape(key),
+                    escape(value),
+                )
+                .ok();
+            }
+            writeln!(html, "</table>").ok();
+        }
+        // Total ion chromatogram
+        if let Some(tic) = &self.tic {
+            writeln!(html, "<h2>Total ion chromatogram</h2>").ok();
+            html.push_str(&tic.svg(SIZE));
+        }
+        // Selected mass spectra
+        if !self.spectra.is_empty() {
+            writeln!(html, "<h2>Mass spectra</h2>").ok();
+            for (title, figure) in &self.spectra {
+                writeln!(html, "<h3>{}</h3>", escape(title)).ok();
+                html.push_str(&figure.svg(SIZE));
+            }
+        }
+        // Table
+        writeln!(html, "<h2>Grouped by retention time</h2>").ok();
+        table(&mut html, &self.table)?;
+        writeln!(
+            html,
+            "<footer>{} {}</footer>",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+        )
+        .ok();
+        writeln!(html, "</body>").ok();
+        writeln!(html, "</html>").ok();
+        Ok(html)
+    }
+}
+
+fn table(html: &mut String, data_frame: &DataFrame) -> PolarsResult<()> {
+    writeln!(html, "<table>").ok();
+    html.push_str("<tr>");
+    for column in data_frame.get_columns() {
+        write!(html, "<th>{}</th>", escape(column.name())).ok();
+    }
+    html.push_str("</tr>\n");
+    for row in 0..data_frame.height() {
+        html.push_str("<tr>");
+        for column in data_frame.get_columns() {
+            let value = match column.get(row)? {
+                AnyValue::Null => Default::default(),
+                _ => column.str_value(row)?,
+            };
+            write!(html, "<td>{}</td>", escape(&value)).ok();
+        }
+        html.push_str("</tr>\n");
+    }
+    writeln!(html, "</table>").ok();
+    Ok(())
+}
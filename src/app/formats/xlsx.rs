@@ -0,0 +1,72 @@
+use anyhow::{ensure, Result};
+use polars::prelude::*;
+use rust_xlsxwriter::{Format, Workbook};
+use std::collections::HashSet;
+
+/// Sheet name length limit
+const NAME_LENGTH: usize = 31;
+/// Sheet rows limit (with the header row)
+const ROWS: usize = 1_048_576;
+
+/// Writes a workbook with a sheet per data frame.
+pub(crate) fn write(sheets: &[(String, DataFrame)]) -> Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let header = Format::new().set_bold();
+    let mut names = HashSet::new();
+    for (name, data_frame) in sheets {
+        ensure!(
+            data_frame.height() < ROWS,
+            "{name} has {} rows, a sheet holds {}",
+            data_frame.height(),
+            ROWS - 1,
+        );
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(unique(name, &mut names))?;
+        for (column, series) in data_frame.get_columns().iter().enumerate() {
+            let column = column as _;
+            worksheet.write_string_with_format(0, column, series.name(), &header)?;
+            for (row, value) in series.iter().enumerate() {
+                let row = row as u32 + 1;
+                match value {
+                    AnyValue::Null => continue,
+                    AnyValue::String(value) => worksheet.write_string(row, column, value)?,
+                    value => match value.extract::<f64>() {
+                        Some(value) => worksheet.write_number(row, column, value)?,
+                        None => worksheet.write_string(row, column, value.to_string())?,
+                    },
+                };
+            }
+        }
+        worksheet.set_freeze_panes(1, 0)?;
+        worksheet.autofit();
+    }
+    Ok(workbook.save_to_buffer()?)
+}
+
+/// Valid unique sheet name (at most 31 characters without `[]:*?/\`)
+fn unique(name: &str, names: &mut HashSet<String>) -> String {
+    let name = name
+        .chars()
+        .map(|char| match char {
+            '[' | ']' | ':' | '*' | '?' | '/' | '\\' => '_',
+            char => char,
+        })
+        .collect::<String>();
+    let name = match name.trim_matches('\'').trim() {
+        "" => "Sheet",
+        name => name,
+    };
+    let mut unique = name.chars().take(NAME_LENGTH).collect::<String>();
+    let mut index = 1;
+    // Names are case insensitive
+    while !names.insert(unique.to_lowercase()) {
+        index += 1;
+        let suffix = format!(" ({index})");
+        unique = name
+            .chars()
+            .take(NAME_LENGTH - suffix.len())
+            .chain(suffix.chars())
+            .collect();
+    }
+    unique
+}
@@ -1,28 +1,36 @@
 use self::panes::{behavior::Behavior, Pane};
-use crate::utils::TreeExt;
-use anyhow::Result;
-use data::{Data, Format};
+use crate::utils::{file, TreeExt};
+use anyhow::{ensure, Context, Result};
+use computers::{TableComputed, TableKey};
+use data::{name, Data};
 use eframe::{get_value, set_value, APP_KEY};
 use egui::{
-    menu::bar, warn_if_debug_build, Align, Align2, CentralPanel, Color32, DroppedFile,
-    FontDefinitions, Id, LayerId, Layout, Order, RichText, ScrollArea, SidePanel, TextStyle,
-    TopBottomPanel,
+    menu::bar, warn_if_debug_build, Align, Align2, CentralPanel, Color32, FontDefinitions, Id,
+    LayerId, Layout, Order, RichText, ScrollArea, SidePanel, TextStyle, TopBottomPanel, Ui, Window,
 };
 use egui_ext::{DroppedFileExt, HoveredFileExt, LightDarkButton};
 use egui_phosphor::{
     add_to_fonts,
     regular::{
-        ARROWS_CLOCKWISE, FLOPPY_DISK, GRID_FOUR, ROCKET, SIDEBAR_SIMPLE, SQUARE_SPLIT_HORIZONTAL,
+        ARROWS_CLOCKWISE, CLOCK_COUNTER_CLOCKWISE, FILE, FILE_XLS, FLOPPY_DISK, FOLDER_OPEN,
+        FOLDER_SIMPLE, GRID_FOUR, ROCKET, SIDEBAR_SIMPLE, SQUARE_SPLIT_HORIZONTAL,
         SQUARE_SPLIT_VERTICAL, TABLE, TABS, TRASH,
     },
     Variant,
 };
 use egui_tiles::{ContainerKind, Tile, Tree};
+use formats::{csv::Delimiter, to_xlsx, Format};
+use itertools::Itertools;
 use panes::table::TablePane;
-use polars::frame::DataFrame;
+use project::Project;
 use serde::{Deserialize, Serialize};
-use std::{fmt::Write, str, time::Duration};
+use std::{
+    fmt::Write,
+    fs::{read, read_dir},
+    path::{Path, PathBuf},
+};
 use tracing::{error, info, trace};
+use windows::{CsvWindow, SaveWindow};
 
 macro icon($icon:expr) {
     RichText::new($icon).size(SIZE)
@@ -34,7 +42,8 @@ macro localize($text:literal) {
 
 /// IEEE 754-2008
 const MAX_PRECISION: usize = 16;
-const _NOTIFICATIONS_DURATION: Duration = Duration::from_secs(15);
+/// Recent files count
+const RECENT: usize = 10;
 const SIZE: f32 = 32.0;
 
 #[derive(Deserialize, Serialize)]
@@ -46,6 +55,19 @@ pub struct App {
     // Panes
     tree: Tree<Pane>,
     behavior: Behavior,
+    // Files
+    recent: Vec<PathBuf>,
+    #[cfg(target_arch = "wasm32")]
+    #[serde(skip)]
+    picked: file::Picked,
+    // Windows
+    #[serde(skip)]
+    csv: Option<CsvWindow>,
+    #[serde(skip)]
+    save: Option<SaveWindow>,
+    /// Last failed menu action
+    #[serde(skip)]
+    error: Option<String>,
 }
 
 impl Default for App {
@@ -55,6 +77,12 @@ impl Default for App {
             left_panel: true,
             tree: Tree::empty("tree"),
             behavior: Default::default(),
+            recent: Vec::new(),
+            #[cfg(target_arch = "wasm32")]
+            picked: Default::default(),
+            csv: None,
+            save: None,
+            error: None,
         }
     }
 }
@@ -104,28 +132,195 @@ impl App {
         }) {
             info!(?dropped_files);
             for dropped_file in dropped_files {
-                // let data_frame: DataFrame = match dropped.extension().and_then(OsStr::to_str) {
-                //     Some("bin") => bincode::deserialize(&fs::read(&args.path)?)?,
-                //     Some("ron") => ron::de::from_str(&fs::read_to_string(&args.path)?)?,
-                //     _ => panic!("unsupported input file extension"),
-                // };
-                match bin(&dropped_file) {
-                    Ok(data_frame) => {
-                        trace!(?data_frame);
-                        self.tree.insert_pane(Pane::Table(TablePane {
-                            data_frame,
-                            settings: Default::default(),
-                        }));
-                    }
-                    Err(error) => {
-                        error!(%error);
-                        // self.toasts
-                        //     .error(format!("{}: {error}", dropped.display()))
-                        //     .set_closable(true)
-                        //     .set_duration(Some(NOTIFICATIONS_DURATION));
-                        continue;
-                    }
-                };
+                // Path on native, content on web
+                match dropped_file.path {
+                    Some(path) => self.load_path(path),
+                    None => match dropped_file.bytes() {
+                        Ok(bytes) => self.load_bytes(dropped_file.name, bytes.to_vec()),
+                        Err(error) => error!(%error),
+                    },
+                }
+            }
+        }
+    }
+
+    /// Opens files picked in a native file dialog.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn pick(&mut self, _ctx: &egui::Context) {
+        for path in file::pick(&extensions()) {
+            self.load_path(path);
+        }
+    }
+
+    /// Opens the browser file picker, the files are loaded in `picked`.
+    #[cfg(target_arch = "wasm32")]
+    fn pick(&mut self, ctx: &egui::Context) {
+        file::pick(ctx, &extensions(), self.picked.clone());
+    }
+
+    /// Loads files read by the browser file picker.
+    #[cfg(target_arch = "wasm32")]
+    fn picked(&mut self) {
+        let picked = self
+            .picked
+            .lock()
+            .map(|mut picked| std::mem::take(&mut *picked))
+            .unwrap_or_default();
+        for (name, bytes) in picked {
+            self.load_bytes(name, bytes);
+        }
+    }
+
+    /// Exports the displayed tables as a workbook with a sheet per table
+    /// pane, named after the first dataset.
+    fn export(&self, ui: &Ui) -> Result<()> {
+        let sheets = self
+            .tree
+            .tiles
+            .iter()
+            .sorted_by_key(|(tile_id, _)| **tile_id)
+            .filter_map(|(_, tile)| match tile {
+                Tile::Pane(Pane::Table(table)) => Some(table),
+                _ => None,
+            })
+            .map(|table| Ok((name(&table.metadata), table.view(ui)?)))
+            .collect::<Result<Vec<_>>>()?;
+        ensure!(!sheets.is_empty(), "there are no tables to export");
+        file::save(&format!("{}.xlsx", sheets[0].0), &to_xlsx(&sheets)?)?;
+        Ok(())
+    }
+
+    /// Saves the workspace as a project file named after the first dataset.
+    fn save_project(&self) -> Result<()> {
+        let name = self
+            .tree
+            .tiles
+            .iter()
+            .sorted_by_key(|(tile_id, _)| **tile_id)
+            .find_map(|(_, tile)| match tile {
+                Tile::Pane(pane) => Some(name(pane.metadata())),
+                _ => None,
+            })
+            .unwrap_or_else(|| "project".to_owned());
+        let project = Project {
+            tree: self.tree.clone(),
+            behavior: self.behavior.clone(),
+        };
+        file::save(
+            &format!("{name}.{}", project::EXTENSION),
+            &project.to_bytes()?,
+        )?;
+        Ok(())
+    }
+
+    /// Loads a file from disk and adds it to the recent files.
+    fn load_path(&mut self, path: PathBuf) {
+        match read_path(&path).and_then(|(bytes, name)| self.load(name, bytes)) {
+            Ok(()) => {
+                self.recent.retain(|recent| *recent != path);
+                self.recent.insert(0, path);
+                self.recent.truncate(RECENT);
+            }
+            Err(error) => {
+                error!(path = %path.display(), %error);
+                self.push_error(format!("{}: {error}", path.display()));
+            }
+        }
+    }
+
+    /// Loads a file content (possibly compressed).
+    fn load_bytes(&mut self, name: String, bytes: Vec<u8>) {
+        let file_name = name.clone();
+        if let Err(error) = decompress(bytes, name).and_then(|(bytes, name)| self.load(name, bytes))
+        {
+            error!(name = %file_name, %error);
+            self.push_error(format!("{file_name}: {error}"));
+        }
+    }
+
+    /// Adds an error to the error window, several files can fail at once.
+    fn push_error(&mut self, error: String) {
+        match &mut self.error {
+            Some(errors) => {
+                errors.push('\n');
+                errors.push_str(&error);
+            }
+            None => self.error = Some(error),
+        }
+    }
+
+    /// Loads a file content into a table pane, delimited text opens the
+    /// column mapping window first, a project replaces the workspace.
+    fn load(&mut self, name: String, bytes: Vec<u8>) -> Result<()> {
+        if extension(&name).as_deref() == Some(project::EXTENSION) {
+            let Project { tree, behavior } = Project::from_bytes(&bytes)?;
+            self.tree = tree;
+            self.behavior = behavior;
+            return Ok(());
+        }
+        let format = Format::detect(&name, &bytes)?;
+        if format == Format::Csv {
+            let delimiter = match extension(&name).as_deref() {
+                Some("tsv") => Delimiter::Tab,
+                _ => Delimiter::Comma,
+            };
+            self.csv = Some(CsvWindow::new(name, bytes, delimiter));
+            return Ok(());
+        }
+        let Data {
+            data_frame,
+            mut metadata,
+        } = format.read(bytes)?;
+        metadata.entry("FileName".to_owned()).or_insert(name);
+        trace!(?data_frame, ?metadata);
+        self.tree.insert_pane(Pane::Table(TablePane {
+            data_frame,
+            metadata,
+            settings: Default::default(),
+            selection: Default::default(),
+            error: None,
+        }));
+        Ok(())
+    }
+}
+
+impl App {
+    fn windows(&mut self, ctx: &egui::Context) {
+        if let Some(window) = &mut self.csv {
+            let (data, open) = window.show(ctx);
+            if let Some(Data {
+                data_frame,
+                metadata,
+            }) = data
+            {
+                trace!(?data_frame, ?metadata);
+                self.tree.insert_pane(Pane::Table(TablePane {
+                    data_frame,
+                    metadata,
+                    settings: Default::default(),
+                    selection: Default::default(),
+                    error: None,
+                }));
+            }
+            if !open {
+                self.csv = None;
+            }
+        }
+        if let Some(window) = &mut self.save {
+            if !window.show(ctx) {
+                self.save = None;
+            }
+        }
+        if let Some(error) = &self.error {
+            let mut open = true;
+            Window::new(localize!("error"))
+                .collapsible(false)
+                .open(&mut open)
+                .show(ctx, |ui| {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                });
+            if !open {
+                self.error = None;
             }
         }
     }
@@ -177,6 +372,58 @@ impl App {
     fn top_panel(&mut self, ctx: &egui::Context) {
         TopBottomPanel::top("top_panel").show(ctx, |ui| {
             bar(ui, |ui| {
+                // File
+                ui.menu_button(icon!(FOLDER_OPEN), |ui| {
+                    if ui.button(format!("{FILE} Open")).clicked() {
+                        ui.close_menu();
+                        self.pick(ui.ctx());
+                    }
+                    if ui
+                        .button(format!("{FILE_XLS} Export tables"))
+                        .on_hover_text(localize!("export_tables_description"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.error = self.export(ui).err().map(|error| {
+                            error!(%error);
+                            error.to_string()
+                        });
+                    }
+                    if ui
+                        .button(format!("{FOLDER_SIMPLE} Save project"))
+                        .on_hover_text(localize!("save_project_description"))
+                        .clicked()
+                    {
+                        ui.close_menu();
+                        self.error = self.save_project().err().map(|error| {
+                            error!(%error);
+                            error.to_string()
+                        });
+                    }
+                    ui.separator();
+                    ui.add_enabled_ui(!self.recent.is_empty(), |ui| {
+                        ui.menu_button(format!("{CLOCK_COUNTER_CLOCKWISE} Recent"), |ui| {
+                            let mut clicked = None;
+                            for path in &self.recent {
+                                if ui.button(path.display().to_string()).clicked() {
+                                    clicked = Some(path.clone());
+                                }
+                            }
+                            ui.separator();
+                            if ui.button(format!("{TRASH} Clear")).clicked() {
+                                self.recent.clear();
+                                ui.close_menu();
+                            }
+                            if let Some(path) = clicked {
+                                ui.close_menu();
+                                self.load_path(path);
+                            }
+                        });
+                    });
+                })
+                .response
+                .on_hover_text(localize!("file"));
+                ui.separator();
                 // Left panel
                 ui.toggle_value(&mut self.left_panel, icon!(SIDEBAR_SIMPLE))
                     .on_hover_text(localize!("left_panel"));
@@ -252,35 +499,32 @@ impl App {
                 ui.separator();
                 // Save
                 ui.menu_button(icon!(FLOPPY_DISK), |ui| {
-                    if ui.button("RON").clicked() {
-                        for tile_id in self.tree.active_tiles() {
-                            if let Some(tile) = self.tree.tiles.get(tile_id) {
-                                match tile {
-                                    Tile::Pane(pane) => {
-                                        Data {
-                                            data_frame: pane.data_frame().clone(),
-                                        }
-                                        .save("df.msv.ron", Format::Ron)
-                                        .unwrap();
-                                    }
-                                    Tile::Container(container) => {}
-                                }
-                            }
-                        }
-                    }
-                    if ui.button("BIN").clicked() {
-                        for tile_id in self.tree.active_tiles() {
-                            if let Some(tile) = self.tree.tiles.get(tile_id) {
-                                match tile {
-                                    Tile::Pane(pane) => {
-                                        Data {
-                                            data_frame: pane.data_frame().clone(),
-                                        }
-                                        .save("df.msv.bin", Format::Ron)
-                                        .unwrap();
-                                    }
-                                    Tile::Container(container) => {}
-                                }
+                    for tile_id in self.tree.active_tiles() {
+                        if let Some(Tile::Pane(pane)) = self.tree.tiles.get(tile_id) {
+                            let data = Data {
+                                data_frame: pane.data_frame().clone(),
+                                metadata: pane.metadata().clone(),
+                            };
+                            if ui
+                                .button(format!("{} {}", pane.title(), data.name()))
+                                .on_hover_text(localize!("save_as"))
+                                .clicked()
+                            {
+                                // Pane output of the filter computer
+                                let settings = match pane {
+                                    Pane::Chromatogram(chromatogram) => &chromatogram.settings,
+                                    Pane::Plot(plot) => &plot.settings,
+                                    Pane::Table(table) => &table.settings,
+                                    Pane::Xic(xic) => &xic.settings,
+                                };
+                                let computed = ui.memory_mut(|memory| {
+                                    memory.caches.cache::<TableComputed>().get(TableKey {
+                                        data_frame: &data.data_frame,
+                                        settings,
+                                    })
+                                });
+                                self.save = Some(SaveWindow::new(data, computed));
+                                ui.close_menu();
                             }
                         }
                     }
@@ -362,17 +606,63 @@ impl eframe::App for App {
     /// Called each time the UI needs repainting, which may be many times per second.
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         self.panels(ctx);
+        self.windows(ctx);
         self.drag_and_drop(ctx);
+        #[cfg(target_arch = "wasm32")]
+        self.picked();
         if self.reactive {
             ctx.request_repaint();
         }
     }
 }
 
-fn bin(dropped_file: &DroppedFile) -> Result<DataFrame> {
-    Ok(bincode::deserialize(&dropped_file.bytes()?)?)
+/// File content (decompressed) and name, an Agilent `.D` directory is read
+/// as its `DATA.MS`
+fn read_path(path: &Path) -> Result<(Vec<u8>, String)> {
+    if path.is_dir() {
+        let entry = read_dir(path)?
+            .filter_map(Result::ok)
+            .find(|entry| entry.file_name().eq_ignore_ascii_case("DATA.MS"))
+            .with_context(|| format!("DATA.MS not found in {}", path.display()))?;
+        let name = Path::new(&path.file_name().unwrap_or_default()).join(entry.file_name());
+        return Ok((read(entry.path())?, name.to_string_lossy().into_owned()));
+    }
+    let name = path
+        .file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+    decompress(read(path)?, name)
+}
+
+/// Decompressed content and name (a project is a zip archive itself)
+fn decompress(bytes: Vec<u8>, name: String) -> Result<(Vec<u8>, String)> {
+    if extension(&name).as_deref() == Some(project::EXTENSION) {
+        return Ok((bytes, name));
+    }
+    Ok(match formats::decompress(&bytes, &name)? {
+        Some(decompressed) => decompressed,
+        None => (bytes, name),
+    })
+}
+
+/// Extensions of the readable formats, compressed files and projects
+fn extensions() -> Vec<&'static str> {
+    Format::ALL
+        .into_iter()
+        .filter(|format| format.capabilities().read)
+        .flat_map(Format::extensions)
+        .copied()
+        .chain(["gz", "zip", "zst", project::EXTENSION])
+        .collect()
+}
+
+/// Lowercase file name extension
+fn extension(name: &str) -> Option<String> {
+    Some(Path::new(name).extension()?.to_str()?.to_lowercase())
 }
 
 mod computers;
 mod data;
+mod formats;
 mod panes;
+mod project;
+mod windows;
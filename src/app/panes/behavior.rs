@@ -5,9 +5,9 @@ use crate::{
     utils::ContainerExt,
 };
 
-use super::{plot::PlotPane, table::TablePane, Pane};
-use egui::{menu::bar, CollapsingHeader, CursorIcon, RichText, Ui, WidgetText};
-use egui_phosphor::regular::{CHART_BAR, LINK, TABLE, X};
+use super::Pane;
+use egui::{menu::bar, CollapsingHeader, CursorIcon, Grid, RichText, Ui, WidgetText};
+use egui_phosphor::regular::{LINK, X};
 use egui_tiles::{Tile, TileId, Tiles, Tree, UiResponse};
 use serde::{Deserialize, Serialize};
 
@@ -40,31 +40,13 @@ impl Behavior {
                 CollapsingHeader::new(RichText::new(pane.title()).heading())
                     .open(open)
                     .show(ui, |ui| {
-                        let text = match pane {
-                            Pane::Plot(_) => TABLE,
-                            Pane::Table(_) => CHART_BAR,
-                        };
+                        let next = pane.next();
                         if ui
-                            .button(icon!(text).size(16.0))
-                            .on_hover_text(localize!("table"))
+                            .button(icon!(next.icon()).size(16.0))
+                            .on_hover_text(next.title())
                             .clicked()
                         {
-                            *pane = match pane {
-                                Pane::Plot(PlotPane {
-                                    data_frame,
-                                    settings,
-                                }) => Pane::Table(TablePane {
-                                    data_frame: data_frame.clone(),
-                                    settings: *settings,
-                                }),
-                                Pane::Table(TablePane {
-                                    data_frame,
-                                    settings,
-                                }) => Pane::Plot(PlotPane {
-                                    data_frame: data_frame.clone(),
-                                    settings: *settings,
-                                }),
-                            };
+                            *pane = next;
                             // if let Some(id) = self.tree.iter {
                             //     // if let Some(Tile::Container(container)) = self.tree.tiles.get_mut(id) {
                             //     //     container.set_kind(ContainerKind::Tabs);
@@ -72,6 +54,20 @@ impl Behavior {
                             // }
                         }
                         pane.settings(ui);
+                        if !pane.metadata().is_empty() {
+                            ui.separator();
+                            CollapsingHeader::new(localize!("metadata"))
+                                .id_source((tile_id, "metadata"))
+                                .show(ui, |ui| {
+                                    Grid::new((tile_id, "grid")).striped(true).show(ui, |ui| {
+                                        for (key, value) in pane.metadata() {
+                                            ui.label(key);
+                                            ui.label(value);
+                                            ui.end_row();
+                                        }
+                                    });
+                                });
+                        }
                     });
             }
         }
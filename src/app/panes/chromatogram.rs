@@ -0,0 +1,190 @@
+use super::{
+    peaks::{markers, table, Detection},
+    settings::Settings,
+};
+use crate::app::{
+    computers::{computed, is_library, ChromatogramComputed, PeaksComputed, PeaksKey, TableKey},
+    data::Metadata,
+};
+use egui::{emath::round_to_decimals, ComboBox, Ui};
+use egui_ext::color;
+use egui_plot::{Line, Plot, PlotPoints};
+use polars::{error::PolarsResult, frame::DataFrame};
+use serde::{Deserialize, Serialize};
+use std::iter::zip;
+use tracing::error;
+
+/// Chromatogram pane
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub(crate) struct ChromatogramPane {
+    pub(crate) data_frame: DataFrame,
+    #[serde(default)]
+    pub(crate) metadata: Metadata,
+    pub(crate) settings: Settings,
+    #[serde(default)]
+    pub(crate) mode: Mode,
+    #[serde(default)]
+    pub(crate) peaks: Detection,
+}
+
+impl ChromatogramPane {
+    pub(super) fn ui(&self, ui: &mut Ui) {
+        if is_library(&self.data_frame) {
+            ui.label("Spectral libraries have no retention time to plot");
+            return;
+        }
+        if let Err(error) = self.plot(ui) {
+            error!(%error);
+            ui.label(error.to_string());
+        }
+    }
+
+    pub(super) fn mode(&mut self, ui: &mut Ui) {
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Mode");
+            ComboBox::from_id_source("mode")
+                .selected_text(self.mode.text())
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.mode, Mode::Tic, Mode::Tic.text())
+                        .on_hover_text(Mode::Tic.description());
+                    ui.selectable_value(&mut self.mode, Mode::Bpc, Mode::Bpc.text())
+                        .on_hover_text(Mode::Bpc.description());
+                })
+                .response
+                .on_hover_text(self.mode.description());
+        });
+    }
+
+    fn plot(&self, ui: &mut Ui) -> PolarsResult<()> {
+        let data_frame = computed(ui.memory_mut(|memory| {
+            memory.caches.cache::<ChromatogramComputed>().get(TableKey {
+                data_frame: &self.data_frame,
+                settings: &self.settings,
+            })
+        }))?;
+        let retention_time = self.settings.retention_time;
+        let signal = match self.mode {
+            Mode::Tic => "Signal",
+            Mode::Bpc => "BasePeak.Signal",
+        };
+        let mut points = Vec::new();
+        let mut base_peaks = Vec::new();
+        for ((time, signal), mass_to_charge) in zip(
+            zip(
+                data_frame["RetentionTime"].i32()?,
+                data_frame[signal].f64()?,
+            ),
+            data_frame["BasePeak.MassToCharge"].f32()?,
+        ) {
+            if let (Some(time), Some(signal)) = (time, signal) {
+                points.push([retention_time.value(time as _) as _, signal]);
+                base_peaks.push(mass_to_charge);
+            }
+        }
+        let units = retention_time.units.abbreviation();
+        let precision = retention_time.precision;
+        let mass_to_charge = self.settings.mass_to_charge.clone();
+        let mode = self.mode;
+        let x = points.iter().map(|&[x, _]| x).collect::<Vec<f64>>();
+        let peaks = if self.peaks.enabled {
+            ui.memory_mut(|memory| {
+                memory.caches.cache::<PeaksComputed>().get(PeaksKey {
+                    points: &points,
+                    settings: &self.peaks,
+                })
+            })
+        } else {
+            Vec::new()
+        };
+        let mut plot = Plot::new("chromatogram");
+        if self.peaks.enabled {
+            // Leave room for the peak list
+            plot = plot.height(ui.available_height() * 2.0 / 3.0);
+        }
+        plot.x_axis_label(format!("Retention time ({units})"))
+            .y_axis_label(mode.y_label())
+            .y_axis_formatter(move |y, _| round_to_decimals(y.value, 5).to_string())
+            .label_formatter(move |_, point| {
+                let mut text = format!(
+                    "Retention time: {:.precision$} {units}\n{}: {}",
+                    point.x,
+                    mode.y_label(),
+                    round_to_decimals(point.y, 5),
+                );
+                if mode == Mode::Bpc {
+                    // Base peak of the nearest scan
+                    let index = x.partition_point(|&x| x < point.x);
+                    let nearest = [index.checked_sub(1), Some(index)]
+                        .into_iter()
+                        .flatten()
+                        .filter(|&index| index < x.len())
+                        .min_by(|&left, &right| {
+                            (x[left] - point.x)
+                                .abs()
+                                .total_cmp(&(x[right] - point.x).abs())
+                        });
+                    if let Some(Some(value)) = nearest.map(|index| base_peaks[index]) {
+                        text.push_str(&format!("\nBase peak: {}", mass_to_charge.format(value)));
+                    }
+                }
+                text
+            })
+            .show(ui, |ui| {
+                let line = Line::new(PlotPoints::from(points))
+                    .name(mode.title())
+                    .color(color(0));
+                ui.line(line);
+                markers(ui, mode.title(), color(0), &peaks);
+            });
+        if self.peaks.enabled {
+            ui.separator();
+            let peaks = peaks
+                .into_iter()
+                .map(|peak| (mode.title(), peak))
+                .collect::<Vec<_>>();
+            table(ui, &peaks, retention_time);
+        }
+        Ok(())
+    }
+}
+
+/// Chromatogram mode
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(crate) enum Mode {
+    /// Total ion chromatogram
+    #[default]
+    Tic,
+    /// Base peak chromatogram
+    Bpc,
+}
+
+impl Mode {
+    pub(crate) const fn title(&self) -> &'static str {
+        match self {
+            Self::Tic => "TIC",
+            Self::Bpc => "BPC",
+        }
+    }
+
+    pub(crate) fn text(&self) -> &'static str {
+        match self {
+            Self::Tic => "Total ion chromatogram",
+            Self::Bpc => "Base peak chromatogram",
+        }
+    }
+
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Self::Tic => "Total signal of each scan",
+            Self::Bpc => "Signal of the most intense peak of each scan",
+        }
+    }
+
+    fn y_label(&self) -> &'static str {
+        match self {
+            Self::Tic => "Total signal",
+            Self::Bpc => "Base peak signal",
+        }
+    }
+}
@@ -1,12 +1,14 @@
 use self::{
     behavior::Behavior,
+    chromatogram::ChromatogramPane,
     plot::PlotPane,
     settings::{Settings, Sort, TimeUnits},
     table::TablePane,
+    xic::XicPane,
 };
-use crate::app::MAX_PRECISION;
+use crate::app::{data::Metadata, MAX_PRECISION};
 use egui::{ComboBox, DragValue, Ui};
-use egui_phosphor::regular::{CHART_BAR, TABLE};
+use egui_phosphor::regular::{CHART_BAR, CHART_LINE, CHART_LINE_UP, TABLE};
 use egui_tiles::TileId;
 use polars::frame::DataFrame;
 use serde::{Deserialize, Serialize};
@@ -14,29 +16,106 @@ use serde::{Deserialize, Serialize};
 /// Pane
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub(crate) enum Pane {
+    Chromatogram(ChromatogramPane),
     Plot(PlotPane),
     Table(TablePane),
+    Xic(XicPane),
 }
 
 impl Pane {
     pub(crate) const fn icon(&self) -> &str {
         match self {
+            Self::Chromatogram(_) => CHART_LINE,
             Self::Plot(_) => CHART_BAR,
             Self::Table(_) => TABLE,
+            Self::Xic(_) => CHART_LINE_UP,
+        }
+    }
+
+    /// Same data and settings in the next pane kind
+    pub(crate) fn next(&self) -> Self {
+        match self {
+            Self::Chromatogram(ChromatogramPane {
+                data_frame,
+                metadata,
+                settings,
+                ..
+            }) => Self::Xic(XicPane {
+                data_frame: data_frame.clone(),
+                metadata: metadata.clone(),
+                settings: settings.clone(),
+                ..Default::default()
+            }),
+            Self::Xic(XicPane {
+                data_frame,
+                metadata,
+                settings,
+                ..
+            }) => Self::Table(TablePane {
+                data_frame: data_frame.clone(),
+                metadata: metadata.clone(),
+                settings: settings.clone(),
+                selection: Default::default(),
+                error: None,
+            }),
+            Self::Plot(PlotPane {
+                data_frame,
+                metadata,
+                settings,
+                ..
+            }) => Self::Chromatogram(ChromatogramPane {
+                data_frame: data_frame.clone(),
+                metadata: metadata.clone(),
+                settings: settings.clone(),
+                ..Default::default()
+            }),
+            Self::Table(TablePane {
+                data_frame,
+                metadata,
+                settings,
+                ..
+            }) => Self::Plot(PlotPane {
+                data_frame: data_frame.clone(),
+                metadata: metadata.clone(),
+                settings: settings.clone(),
+                ..Default::default()
+            }),
         }
     }
 
     pub(crate) const fn title(&self) -> &'static str {
         match self {
+            Self::Chromatogram(chromatogram) => chromatogram.mode.title(),
             Self::Plot(_) => "Plot",
             Self::Table(_) => "Table",
+            Self::Xic(_) => "XIC",
         }
     }
 
     pub(crate) const fn data_frame(&self) -> &DataFrame {
         match self {
+            Self::Chromatogram(chromatogram) => &chromatogram.data_frame,
             Self::Plot(plot) => &plot.data_frame,
             Self::Table(table) => &table.data_frame,
+            Self::Xic(xic) => &xic.data_frame,
+        }
+    }
+
+    pub(crate) fn data_frame_mut(&mut self) -> &mut DataFrame {
+        match self {
+            Self::Chromatogram(chromatogram) => &mut chromatogram.data_frame,
+            Self::Plot(plot) => &mut plot.data_frame,
+            Self::Table(table) => &mut table.data_frame,
+            Self::Xic(xic) => &mut xic.data_frame,
+        }
+    }
+
+    pub(crate) const fn metadata(&self) -> &Metadata {
+        match self {
+            Self::Chromatogram(chromatogram) => &chromatogram.metadata,
+            Self::Plot(plot) => &plot.metadata,
+            Self::Table(table) => &table.metadata,
+            Self::Xic(xic) => &xic.metadata,
         }
     }
 }
@@ -44,21 +123,44 @@ impl Pane {
 impl Pane {
     pub(crate) fn ui(&mut self, ui: &mut Ui) {
         match self {
+            Self::Chromatogram(chromatogram) => chromatogram.ui(ui),
             Self::Plot(plot) => plot.ui(ui),
             Self::Table(table) => table.ui(ui),
+            Self::Xic(xic) => xic.ui(ui),
         }
     }
 
     pub(crate) fn settings(&mut self, ui: &mut Ui) {
         match self {
-            Self::Plot(plot) => plot.settings.ui(ui),
-            Self::Table(table) => table.settings.ui(ui),
+            Self::Chromatogram(chromatogram) => {
+                chromatogram.settings.ui(ui);
+                chromatogram.mode(ui);
+                chromatogram.peaks.ui(ui);
+            }
+            Self::Plot(plot) => {
+                plot.settings.ui(ui);
+                plot.export(ui);
+                plot.peaks.ui(ui);
+            }
+            Self::Table(table) => {
+                table.settings.ui(ui);
+                table.export(ui);
+                table.selection(ui);
+            }
+            Self::Xic(xic) => {
+                xic.settings.ui(ui);
+                xic.ions(ui);
+                xic.peaks.ui(ui);
+            }
         }
     }
 }
 
 pub(crate) mod behavior;
+pub(crate) mod chromatogram;
+pub(crate) mod peaks;
 pub(crate) mod plot;
 pub(crate) mod settings;
 pub(crate) mod table;
 pub(crate) mod widgets;
+pub(crate) mod xic;
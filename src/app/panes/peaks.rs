@@ -0,0 +1,207 @@
+use super::settings::RetentionTime;
+use crate::app::computers::peaks::Peak;
+use egui::{Color32, ComboBox, Direction, DragValue, Layout, Ui};
+use egui_ext::TableRowExt;
+use egui_extras::{Column, TableBuilder};
+use egui_plot::{MarkerShape, PlotUi, Points};
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+
+/// Chromatographic peak detection settings
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Detection {
+    pub(crate) enabled: bool,
+    pub(crate) algorithm: Algorithm,
+    /// Minimum width (scans)
+    pub(crate) min_width: usize,
+    /// Minimum apex signal
+    pub(crate) min_height: f64,
+    /// Minimum apex signal above the higher boundary
+    pub(crate) min_prominence: f64,
+    /// Minimum prominence to noise ratio
+    pub(crate) signal_to_noise: f64,
+}
+
+impl Detection {
+    pub(crate) fn ui(&mut self, ui: &mut Ui) {
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Peaks");
+            ui.checkbox(&mut self.enabled, "")
+                .on_hover_text("Detect chromatographic peaks");
+        });
+        if !self.enabled {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Algorithm");
+            ComboBox::from_id_source("peak_algorithm")
+                .selected_text(self.algorithm.text())
+                .show_ui(ui, |ui| {
+                    for algorithm in [Algorithm::Derivative, Algorithm::Wavelet] {
+                        ui.selectable_value(&mut self.algorithm, algorithm, algorithm.text())
+                            .on_hover_text(algorithm.description());
+                    }
+                })
+                .response
+                .on_hover_text(self.algorithm.description());
+        });
+        ui.horizontal(|ui| {
+            ui.label("Min width");
+            ui.add(
+                DragValue::new(&mut self.min_width)
+                    .range(1..=usize::MAX)
+                    .suffix(" scans"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Min height");
+            ui.add(DragValue::new(&mut self.min_height).range(0.0..=f64::MAX))
+                .on_hover_text("Minimum apex signal");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Min prominence");
+            ui.add(DragValue::new(&mut self.min_prominence).range(0.0..=f64::MAX))
+                .on_hover_text("Minimum apex signal above the higher boundary");
+        });
+        ui.horizontal(|ui| {
+            ui.label("S/N");
+            ui.add(
+                DragValue::new(&mut self.signal_to_noise)
+                    .range(0.0..=f64::MAX)
+                    .speed(0.1),
+            )
+            .on_hover_text("Minimum prominence to noise ratio");
+        });
+    }
+}
+
+impl Default for Detection {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            algorithm: Algorithm::Derivative,
+            min_width: 5,
+            min_height: 0.0,
+            min_prominence: 0.0,
+            signal_to_noise: 3.0,
+        }
+    }
+}
+
+impl Hash for Detection {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.enabled.hash(state);
+        self.algorithm.hash(state);
+        self.min_width.hash(state);
+        self.min_height.to_bits().hash(state);
+        self.min_prominence.to_bits().hash(state);
+        self.signal_to_noise.to_bits().hash(state);
+    }
+}
+
+/// Peak detection algorithm
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(crate) enum Algorithm {
+    #[default]
+    Derivative,
+    Wavelet,
+}
+
+impl Algorithm {
+    pub(crate) fn text(&self) -> &'static str {
+        match self {
+            Self::Derivative => "Derivative",
+            Self::Wavelet => "Wavelet",
+        }
+    }
+
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Self::Derivative => "Sign changes of the first derivative of the smoothed trace",
+            Self::Wavelet => "Maxima of the continuous wavelet transform (Ricker wavelet)",
+        }
+    }
+}
+
+/// Apex and boundary markers of the trace peaks
+pub(crate) fn markers(ui: &mut PlotUi, name: &str, color: Color32, peaks: &[Peak]) {
+    let name = format!("{name} peaks");
+    let apexes = peaks.iter().map(|peak| peak.apex).collect::<Vec<_>>();
+    let boundaries = peaks
+        .iter()
+        .flat_map(|peak| [peak.start, peak.end])
+        .collect::<Vec<_>>();
+    ui.points(
+        Points::new(apexes)
+            .name(&name)
+            .color(color)
+            .shape(MarkerShape::Down)
+            .filled(true)
+            .radius(4.0_f32),
+    );
+    ui.points(
+        Points::new(boundaries)
+            .name(&name)
+            .color(color)
+            .shape(MarkerShape::Circle)
+            .filled(false)
+            .radius(3.0_f32),
+    );
+}
+
+/// Peak list (trace name, peak), retention times in the settings units
+pub(crate) fn table(ui: &mut Ui, peaks: &[(&str, Peak)], retention_time: RetentionTime) {
+    let width = ui.spacing().interact_size.x;
+    let height = ui.spacing().interact_size.y;
+    let precision = retention_time.precision;
+    let units = retention_time.units.abbreviation();
+    TableBuilder::new(ui)
+        .cell_layout(Layout::centered_and_justified(Direction::LeftToRight))
+        .column(Column::auto_with_initial_suggestion(width))
+        .columns(Column::auto(), 5)
+        .auto_shrink(false)
+        .striped(true)
+        .header(height, |mut row| {
+            row.col(|ui| {
+                ui.heading("Index");
+            });
+            row.col(|ui| {
+                ui.heading("Trace");
+            });
+            row.col(|ui| {
+                ui.heading(format!("Apex ({units})"));
+            });
+            row.col(|ui| {
+                ui.heading("Height");
+            });
+            row.col(|ui| {
+                ui.heading(format!("Start ({units})"));
+            });
+            row.col(|ui| {
+                ui.heading(format!("End ({units})"));
+            });
+        })
+        .body(|body| {
+            body.rows(height, peaks.len(), |mut row| {
+                let row_index = row.index();
+                let (name, peak) = peaks[row_index];
+                row.col(|ui| {
+                    ui.label(row_index.to_string());
+                });
+                row.left_align_col(|ui| {
+                    ui.label(name);
+                });
+                let time = |ui: &mut Ui, value: f64| {
+                    ui.label(format!("{value:.precision$}"))
+                        .on_hover_text(value.to_string());
+                };
+                row.left_align_col(|ui| time(ui, peak.apex[0]));
+                row.left_align_col(|ui| {
+                    ui.label(peak.apex[1].to_string());
+                });
+                row.left_align_col(|ui| time(ui, peak.start[0]));
+                row.left_align_col(|ui| time(ui, peak.end[0]));
+            });
+        });
+}
@@ -1,279 +1,312 @@
-use super::settings::{Settings, Sort};
-use crate::app::computers::{TableComputed, TableKey};
-use egui::{emath::round_to_decimals, Align2, RichText, Ui, Vec2};
+use super::{
+    peaks::{markers, table, Detection},
+    settings::{MassToCharge, RetentionTime, Settings, Sort},
+};
+use crate::{
+    app::{
+        computers::{computed, is_library, PeaksComputed, PeaksKey, TableComputed, TableKey},
+        data::{name, Metadata},
+        formats::figure::{Figure, Item, Kind},
+    },
+    utils::file,
+};
+use anyhow::Result;
+use egui::{emath::round_to_decimals, DragValue, RichText, Ui};
 use egui_ext::color;
-use egui_plot::{Bar, BarChart, Legend, Line, Plot, PlotMemory, PlotPoint, PlotPoints, Text};
-use polars::{error::PolarsResult, frame::DataFrame};
+use egui_phosphor::regular::{FILE_PNG, FILE_SVG};
+use egui_plot::{Bar, BarChart, Legend, Line, Plot, PlotPoints};
+use polars::{
+    datatypes::DataType,
+    error::{polars_bail, PolarsResult},
+    frame::DataFrame,
+};
 use serde::{Deserialize, Serialize};
-use std::iter::{empty, zip};
+use std::iter::zip;
 use tracing::error;
 
 /// Plot pane
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub(crate) struct PlotPane {
     pub(crate) data_frame: DataFrame,
+    #[serde(default)]
+    pub(crate) metadata: Metadata,
     pub(crate) settings: Settings,
+    #[serde(default)]
+    pub(crate) export: Export,
+    #[serde(default)]
+    pub(crate) peaks: Detection,
+    /// Visible bounds of the last frame
+    #[serde(skip)]
+    pub(crate) bounds: Option<[[f64; 2]; 2]>,
+    /// Last export error
+    #[serde(skip)]
+    pub(crate) error: Option<String>,
 }
 
 impl PlotPane {
-    pub(super) fn ui(&self, ui: &mut Ui) {
-        match self.settings.sort {
-            Sort::RetentionTime if !self.settings.explode => self.grouped_by_retention_time(ui),
-            Sort::MassToCharge if !self.settings.explode => self.grouped_by_mass_to_charge(ui),
-            _ => unimplemented!(),
+    pub(super) fn ui(&mut self, ui: &mut Ui) {
+        if is_library(&self.data_frame) {
+            ui.label("Spectral libraries have no retention time to plot");
+            return;
         }
-    }
-
-    pub(super) fn grouped_by_mass_to_charge(&self, ui: &mut Ui) {
-        let data_frame = ui.memory_mut(|memory| {
-            memory.caches.cache::<TableComputed>().get(TableKey {
-                data_frame: &self.data_frame,
-                settings: &self.settings,
+        let figure = match self.figure(ui) {
+            Ok(figure) => figure,
+            Err(error) => {
+                error!(%error);
+                ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+                return;
+            }
+        };
+        // Peaks of the lines (extracted ion chromatograms)
+        let peaks = figure
+            .items
+            .iter()
+            .map(|item| match &item.kind {
+                Kind::Line(points) if self.peaks.enabled => ui.memory_mut(|memory| {
+                    memory.caches.cache::<PeaksComputed>().get(PeaksKey {
+                        points,
+                        settings: &self.peaks,
+                    })
+                }),
+                _ => Vec::new(),
             })
-        });
-        // let points = data_frame.height();
-        let mass_to_charge = data_frame["MassToCharge"].f32().unwrap();
-        let retention_time = data_frame["RetentionTime"].list().unwrap();
-        let signal = data_frame["Signal"].list().unwrap();
+            .collect::<Vec<_>>();
         ui.vertical_centered_justified(|ui| {
-            // let id = ui.make_persistent_id("plot");
-            // let plot_memory = PlotMemory::load(ui.ctx(), id);
             let mut plot = Plot::new("plot")
+                .x_axis_label(&figure.x_label)
+                .y_axis_label(&figure.y_label)
                 .y_axis_formatter(move |y, _| round_to_decimals(y.value, 5).to_string());
-            if self.settings.legend {
-                let mut legend = Legend::default();
-                // if let Some(visible) = self.settings.visible.take() {
-                //     legend = if visible {
-                //         legend.hidden_items(empty())
-                //     } else {
-                //         let hidden_items = mass_to_charge
-                //             .iter()
-                //             .filter_map(|mass_to_charge| Some(mass_to_charge?.to_string()));
-                //         legend.hidden_items(hidden_items)
-                //     };
-                // }
-                plot = plot.legend(legend);
+            if figure.legend {
+                plot = plot.legend(Legend::default());
             }
-            plot.show(ui, |ui| {
-                // let bounds = ui.plot_bounds().range_x();
-                // let width = ui.plot_bounds().width();
-                // tracing::error!(?width);
-
-                // Lines
-                for (mass_to_charge, retention_time, signal) in
-                    zip(mass_to_charge, zip(retention_time, signal)).filter_map(
-                        |(mass_to_charge, (retention_time, signal))| {
-                            Some((mass_to_charge?, retention_time?, signal?))
-                        },
-                    )
-                {
-                    let line = Line::new(PlotPoints::from_iter(
-                        zip(retention_time.i32().unwrap(), signal.u16().unwrap()).filter_map(
-                            |(retention_time, signal)| Some([retention_time? as _, signal? as _]),
-                        ),
-                    ))
-                    .name(mass_to_charge.to_string());
-                    ui.line(line);
+            if self.peaks.enabled {
+                // Leave room for the peak list
+                plot = plot.height(ui.available_height() * 2.0 / 3.0);
+            }
+            let response = plot.show(ui, |ui| {
+                for (item, peaks) in zip(&figure.items, &peaks) {
+                    match &item.kind {
+                        Kind::Line(points) => {
+                            let line = Line::new(PlotPoints::from(points.clone()))
+                                .name(&item.name)
+                                .color(item.color);
+                            ui.line(line);
+                            markers(ui, &item.name, item.color, peaks);
+                        }
+                        Kind::Bars { width, bars } => {
+                            let bars = bars
+                                .iter()
+                                .map(|([x, y], name)| Bar::new(*x, *y).width(*width).name(name))
+                                .collect();
+                            let chart = BarChart::new(bars).name(&item.name).color(item.color);
+                            ui.bar_chart(chart);
+                        }
+                    }
                 }
-
-                // // Bars
-                // for (mass_to_charge, retention_time, signal) in
-                //     zip(mass_to_charge, zip(retention_time, signal)).filter_map(
-                //         |(mass_to_charge, (retention_time, signal))| {
-                //             Some((mass_to_charge?, retention_time?, signal?))
-                //         },
-                //     )
-                // {
-                //     let bars = zip(retention_time.i32().unwrap(), signal.u16().unwrap())
-                //         .filter_map(|(retention_time, signal)| {
-                //             Some(Bar::new(retention_time? as _, signal? as _))
-                //         })
-                //         .collect();
-                //     let chart = BarChart::new(bars).name(mass_to_charge.to_string());
-                //     ui.bar_chart(chart);
-                // }
-
-                // let mut charts = Vec::new();
-                // for (mass_to_charge, retention_time, signal) in
-                //     zip(mass_to_charge, zip(retention_time, signal)).filter_map(
-                //         |(mass_to_charge, (retention_time, signal))| {
-                //             Some((mass_to_charge?, retention_time?, signal?))
-                //         },
-                //     )
-                // {
-                //     let bars = zip(retention_time.i32().unwrap(), signal.().unwrap())
-                //         .filter_map(|(retention_time, signal)| {
-                //             Some(Bar::new(retention_time? as _, signal? as _))
-                //         })
-                //         .collect();
-                //     let chart = BarChart::new(bars)
-                //         .name(mass_to_charge.to_string())
-                //         .stack_on(&charts.iter().collect::<Vec<_>>());
-                //     charts.push(chart);
-                // }
-                // for chart in charts {
-                //     ui.bar_chart(chart);
-                // }
-
-                // Bars
-                // for (retention_time, (signal, peak)) in zip(retention_time, zip(signal, peak)) {
-                //     // let mut offset = 0.0;
-                //     if let (Some(retention_time), Some(signal), Some(peak)) =
-                //         (retention_time, signal, peak)
-                //     {
-                //         if width > 10000.0 {
-                //             let bar = Bar::new(retention_time as _, signal as _)
-                //                 .name(retention_time.to_string());
-                //             let chart = BarChart::new(vec![bar]).name(retention_time.to_string());
-                //             // .color(color(retention_time as _));
-                //             ui.bar_chart(chart);
-                //         } else {
-                //             let fields = peak.struct_().unwrap().fields();
-                //             // tracing::error!(?peak);
-                //             // let chart = BarChart::new(vec![bar]).name(retention_time.to_string());
-                //         }
-                //     }
-                // }
-
-                // for (retention_time, chunk) in &zip(retention_time, zip(mass_to_charge, signal))
-                //     .chunk_by(|(retention_time, _)| *retention_time)
-                // {
-                //     let mut offset = 0.0;
-                //     let mut bars = Vec::new();
-                //     if let Some(retention_time) = retention_time {
-                //         for (_, (mass_to_charge, signal)) in chunk {
-                //             if let (Some(mass_to_charge), Some(signal)) = (mass_to_charge, signal) {
-                //                 let bar = Bar::new(retention_time as _, signal as _)
-                //                     .name(mass_to_charge.to_string())
-                //                     .base_offset(offset as _);
-                //                 bars.push(bar);
-                //                 offset += signal;
-                //             }
-                //         }
-                //         let chart = BarChart::new(bars).name(retention_time.to_string());
-                //         // .color(color(retention_time as _));
-                //         ui.bar_chart(chart);
-                //     }
-                // }
-
-                // let mut iter = zip(retention_time.into_iter(), signal.into_iter());
-                // while let Some((Some(x), Some(y))) = iter.next() {
-                //     let bar = Bar::new(x as _, y as _).name("x");
-                //     let chart = BarChart::new(vec![bar]).name(x).color(color(x as _));
-                //     ui.bar_chart(chart);
-                // }
-
-                // for (key, values) in visualized {
-                //     // Bars
-                //     let mut offset = 0.0;
-                //     let x = key.into_inner();
-                //     for (name, value) in values {
-                //         let mut y = value;
-                //         if percent {
-                //             y *= 100.0;
-                //         }
-                //         let bar = Bar::new(x, y).name(name).base_offset(offset);
-                //         let chart = BarChart::new(vec![bar])
-                //             .width(context.settings.visualization.width)
-                //             .name(x)
-                //             .color(color(x as _));
-                //         ui.bar_chart(chart);
-                //         offset += y;
-                //     }
-                //     // Text
-                //     if context.settings.visualization.text.show
-                //         && offset >= context.settings.visualization.text.min
-                //     {
-                //         let y = offset;
-                //         let text = Text::new(
-                //             PlotPoint::new(x, y),
-                //             RichText::new(format!("{y:.p$}"))
-                //                 .size(context.settings.visualization.text.size)
-                //                 .heading(),
-                //         )
-                //         .name(x)
-                //         .color(color(x as _))
-                //         .anchor(Align2::CENTER_BOTTOM);
-                //         ui.text(text);
-                //     }
-                // }
             });
+            let bounds = response.transform.bounds();
+            self.bounds = Some([bounds.min(), bounds.max()]);
         });
+        if self.peaks.enabled {
+            ui.separator();
+            let peaks = zip(&figure.items, &peaks)
+                .flat_map(|(item, peaks)| peaks.iter().map(|&peak| (item.name.as_str(), peak)))
+                .collect::<Vec<_>>();
+            table(ui, &peaks, self.settings.retention_time);
+        }
     }
 
-    pub(super) fn grouped_by_retention_time(&self, ui: &mut Ui) {
-        let data_frame = ui.memory_mut(|memory| {
+    /// Figure of the computed data frame, as drawn on screen
+    pub(crate) fn figure(&self, ui: &Ui) -> PolarsResult<Figure> {
+        let data_frame = computed(ui.memory_mut(|memory| {
             memory.caches.cache::<TableComputed>().get(TableKey {
                 data_frame: &self.data_frame,
                 settings: &self.settings,
             })
-        });
-        let total_rows = data_frame.height();
-        let retention_time = data_frame["RetentionTime"].i32().unwrap();
-        let mass_spectrum = self.data_frame["MassSpectrum"].list().unwrap();
-        let mut plot = Plot::new("plot")
-            .y_axis_formatter(move |y, _| round_to_decimals(y.value, 5).to_string());
-        if self.settings.legend {
-            let mut legend = Legend::default();
-            // if let Some(visible) = self.settings.visible.take() {
-            //     legend = if visible {
-            //         legend.hidden_items(empty())
-            //     } else {
-            //         let hidden_items = retention_time
-            //             .iter()
-            //             .filter_map(|retention_time| Some(retention_time?.to_string()));
-            //         legend.hidden_items(hidden_items)
-            //     };
-            // }
-            plot = plot.legend(legend);
+        }))?;
+        let retention_time = self.settings.retention_time;
+        if self.settings.explode {
+            polars_bail!(InvalidOperation: "unsupported view: exploded peaks can't be plotted");
         }
-        plot.show(ui, |ui| {
-            let range_x = ui.plot_bounds().range_x();
-            // let width = ui.plot_bounds().width();
-            // tracing::error!(?width);
+        let items = match self.settings.sort {
+            Sort::RetentionTime => grouped_by_retention_time(&data_frame, retention_time)?,
+            Sort::MassToCharge => grouped_by_mass_to_charge(
+                &data_frame,
+                &self.settings.mass_to_charge,
+                retention_time,
+            )?,
+        };
+        Ok(Figure {
+            x_label: format!("Retention time ({})", retention_time.units.abbreviation()),
+            y_label: "Signal".to_owned(),
+            legend: self.settings.legend,
+            bounds: self.bounds,
+            items,
+        })
+    }
 
-            // Bars
-            let mut bars = Vec::new();
-            // for row_index in 0..total_rows {
-            //     let retention_time = retention_time.get(row_index).unwrap();
-            //     let mass_spectrum = mass_spectrum.get_as_series(row_index).unwrap();
-            //     let mass_spectrum = mass_spectrum.struct_().unwrap();
-            //     let mass_to_charge = mass_spectrum.field_by_name("MassToCharge").unwrap();
-            //     let mass_to_charge = mass_to_charge.f32().unwrap();
-            //     let signal = mass_spectrum.field_by_name("Signal").unwrap();
-            //     let signal = signal.u16().unwrap();
-            //     for (mass_to_charge, signal) in zip(mass_to_charge, signal) {
-            //         let mass_to_charge = mass_to_charge.unwrap();
-            //         let signal = signal.unwrap();
-            //         let bar =
-            //             Bar::new(retention_time as _, signal as _).name(mass_to_charge.to_string());
-            //         bars.push(bar);
-            //     }
-            // }
-            for (retention_time, mass_spectrum) in zip(retention_time, mass_spectrum) {
-                let retention_time = retention_time.unwrap();
-                let mass_spectrum = mass_spectrum.unwrap();
-                let mass_spectrum = mass_spectrum.struct_().unwrap();
-                let mass_to_charge = mass_spectrum.field_by_name("MassToCharge").unwrap();
-                let mass_to_charge = mass_to_charge.f32().unwrap();
-                let signal = mass_spectrum.field_by_name("Signal").unwrap();
-                let signal = signal.u16().unwrap();
-                // error!(retention_time, ?range_x);
-                let retention_time = retention_time as _;
-                // if range_x.start().floor() <= retention_time
-                //     && retention_time <= range_x.end().ceil()
-                // {
-                for (mass_to_charge, signal) in zip(mass_to_charge, signal) {
-                    let mass_to_charge = mass_to_charge.unwrap();
-                    let signal = signal.unwrap();
-                    let bar =
-                        Bar::new(retention_time, signal as _).name(mass_to_charge.to_string());
-                    bars.push(bar);
-                }
-                // }
+    pub(super) fn export(&mut self, ui: &mut Ui) {
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Size");
+            ui.add(
+                DragValue::new(&mut self.export.width)
+                    .range(64.0..=8192.0)
+                    .suffix(" pt"),
+            );
+            ui.label("×");
+            ui.add(
+                DragValue::new(&mut self.export.height)
+                    .range(64.0..=8192.0)
+                    .suffix(" pt"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Scale");
+            ui.add(
+                DragValue::new(&mut self.export.scale)
+                    .range(1.0..=8.0)
+                    .speed(0.1)
+                    .suffix("×"),
+            )
+            .on_hover_text("PNG pixels per point");
+        });
+        let mut result = None;
+        ui.horizontal(|ui| {
+            ui.label("Export");
+            if ui
+                .button(RichText::new(format!("{FILE_SVG} SVG")))
+                .on_hover_text("Export the plot as a vector image")
+                .clicked()
+            {
+                result = Some(self.svg(ui));
+            }
+            if ui
+                .button(RichText::new(format!("{FILE_PNG} PNG")))
+                .on_hover_text("Export the plot as a raster image")
+                .clicked()
+            {
+                result = Some(self.png(ui));
             }
-            let chart = BarChart::new(bars);
-            ui.bar_chart(chart);
         });
+        if let Some(result) = result {
+            self.error = result.err().map(|error| {
+                error!(%error);
+                error.to_string()
+            });
+        }
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn svg(&self, ui: &Ui) -> Result<()> {
+        let svg = self.figure(ui)?.svg(self.export.size());
+        file::save(&format!("{}.svg", name(&self.metadata)), svg.as_bytes())?;
+        Ok(())
+    }
+
+    fn png(&self, ui: &Ui) -> Result<()> {
+        let bytes = self
+            .figure(ui)?
+            .png(self.export.size(), self.export.scale)?;
+        file::save(&format!("{}.png", name(&self.metadata)), &bytes)?;
+        Ok(())
+    }
+}
+
+/// Export settings
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Export {
+    pub(crate) width: f64,
+    pub(crate) height: f64,
+    pub(crate) scale: f32,
+}
+
+impl Export {
+    fn size(&self) -> [f64; 2] {
+        [self.width, self.height]
+    }
+}
+
+impl Default for Export {
+    fn default() -> Self {
+        Self {
+            width: 800.0,
+            height: 500.0,
+            scale: 2.0,
+        }
+    }
+}
+
+/// Extracted ion chromatogram lines, one per mass to charge window
+fn grouped_by_mass_to_charge(
+    data_frame: &DataFrame,
+    settings: &MassToCharge,
+    retention_time: RetentionTime,
+) -> PolarsResult<Vec<Item>> {
+    let mass_to_charge = data_frame["MassToCharge"].f32()?;
+    let extracted_ion_chromatogram = data_frame["ExtractedIonChromatogram"].list()?;
+    let mut items = Vec::new();
+    for (index, (mass_to_charge, extracted_ion_chromatogram)) in
+        zip(mass_to_charge, extracted_ion_chromatogram).enumerate()
+    {
+        let (Some(mass_to_charge), Some(extracted_ion_chromatogram)) =
+            (mass_to_charge, extracted_ion_chromatogram)
+        else {
+            continue;
+        };
+        let extracted_ion_chromatogram = extracted_ion_chromatogram.struct_()?;
+        let time = extracted_ion_chromatogram.field_by_name("RetentionTime")?;
+        let signal = extracted_ion_chromatogram
+            .field_by_name("Signal")?
+            .cast(&DataType::Float64)?;
+        let points = zip(time.i32()?, signal.f64()?)
+            .filter_map(|(time, signal)| Some([retention_time.value(time? as _) as _, signal?]))
+            .collect();
+        items.push(Item {
+            name: format!(
+                "{} ± {}",
+                settings.format(mass_to_charge),
+                settings.tolerance
+            ),
+            color: color(index),
+            kind: Kind::Line(points),
+        });
+    }
+    Ok(items)
+}
+
+/// Mass spectrum bars at their retention time
+fn grouped_by_retention_time(
+    data_frame: &DataFrame,
+    retention_time: RetentionTime,
+) -> PolarsResult<Vec<Item>> {
+    let time = data_frame["RetentionTime"].i32()?;
+    let mass_spectrum = data_frame["MassSpectrum"].list()?;
+    let mut bars = Vec::new();
+    for (time, mass_spectrum) in zip(time, mass_spectrum) {
+        let (Some(time), Some(mass_spectrum)) = (time, mass_spectrum) else {
+            continue;
+        };
+        let time = retention_time.value(time as _) as f64;
+        let mass_spectrum = mass_spectrum.struct_()?;
+        let mass_to_charge = mass_spectrum.field_by_name("MassToCharge")?;
+        let signal = mass_spectrum
+            .field_by_name("Signal")?
+            .cast(&DataType::Float64)?;
+        for (mass_to_charge, signal) in zip(mass_to_charge.f32()?, signal.f64()?) {
+            if let (Some(mass_to_charge), Some(signal)) = (mass_to_charge, signal) {
+                bars.push(([time, signal], mass_to_charge.to_string()));
+            }
+        }
     }
+    Ok(vec![Item {
+        name: String::new(),
+        color: color(0),
+        kind: Kind::Bars {
+            width: retention_time.value(0.5) as _,
+            bars,
+        },
+    }])
 }
@@ -1,6 +1,10 @@
-use std::fmt::{self, Display, Formatter};
+use std::{
+    fmt::{self, Display, Formatter},
+    hash::{Hash, Hasher},
+};
 
-use egui::{ComboBox, DragValue, Ui, WidgetText};
+use egui::{ComboBox, DragValue, RichText, Ui, WidgetText};
+use egui_phosphor::regular::{PLUS, X};
 use serde::{Deserialize, Serialize};
 use uom::si::{
     f32::Time,
@@ -10,13 +14,16 @@ use uom::si::{
 use crate::app::MAX_PRECISION;
 
 /// Settings
-#[derive(Clone, Copy, Debug, Default, Deserialize, Hash, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Hash, PartialEq, Serialize)]
 pub(crate) struct Settings {
     pub(crate) explode: bool,
     pub(crate) filter_null: bool,
     pub(crate) mass_to_charge: MassToCharge,
     pub(crate) retention_time: RetentionTime,
     pub(crate) sort: Sort,
+    /// Sort the scans by base peak (grouped by retention time)
+    #[serde(default)]
+    pub(crate) base_peak: bool,
 
     pub(crate) normalize: bool,
 
@@ -96,6 +103,16 @@ impl Settings {
                 .response
                 .on_hover_text(self.sort.description());
         });
+        if self.sort == Sort::RetentionTime && !self.explode {
+            ui.horizontal(|ui| {
+                ui.label("Base peak");
+                ui.checkbox(&mut self.base_peak, "")
+                    .on_hover_text("Sort scans by base peak mass to charge");
+            });
+        }
+        if self.sort == Sort::MassToCharge && !self.explode {
+            self.mass_to_charge.extraction(ui);
+        }
         ui.separator();
         ui.horizontal(|ui| {
             ui.label("Normalize");
@@ -139,24 +156,216 @@ impl Sort {
 }
 
 /// Mass to charge settings
-#[derive(Clone, Copy, Debug, Deserialize, Hash, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub(crate) struct MassToCharge {
     pub(crate) precision: usize,
+    /// Extracted ion chromatogram window tolerance
+    #[serde(default)]
+    pub(crate) tolerance: Tolerance,
+    /// Extracted ion chromatogram window centering
+    #[serde(default)]
+    pub(crate) centering: Centering,
+    /// Window centers of the list centering
+    #[serde(default)]
+    pub(crate) list: Vec<f32>,
 }
 
 impl Default for MassToCharge {
     fn default() -> Self {
-        Self { precision: 1 }
+        Self {
+            precision: 1,
+            tolerance: Default::default(),
+            centering: Default::default(),
+            list: Vec::new(),
+        }
+    }
+}
+
+impl Hash for MassToCharge {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.precision.hash(state);
+        self.tolerance.hash(state);
+        self.centering.hash(state);
+        for value in &self.list {
+            value.to_bits().hash(state);
+        }
     }
 }
 
 impl MassToCharge {
-    pub(crate) fn format(self, value: f32) -> MassToChargeFormat {
+    pub(crate) fn format(&self, value: f32) -> MassToChargeFormat {
         MassToChargeFormat {
             value,
             precision: Some(self.precision),
         }
     }
+
+    /// Extracted ion chromatogram settings
+    fn extraction(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Tolerance");
+            self.tolerance.ui(ui, "tolerance_units");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Centering");
+            ComboBox::from_id_source("centering")
+                .selected_text(self.centering.text())
+                .show_ui(ui, |ui| {
+                    for centering in [Centering::Nominal, Centering::List, Centering::Centroid] {
+                        ui.selectable_value(&mut self.centering, centering, centering.text())
+                            .on_hover_text(centering.description());
+                    }
+                })
+                .response
+                .on_hover_text(self.centering.description());
+        });
+        if self.centering == Centering::List {
+            let mut remove = None;
+            for (index, value) in self.list.iter_mut().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.add(
+                        DragValue::new(value)
+                            .range(0.0..=f32::MAX)
+                            .speed(0.01)
+                            .max_decimals(MAX_PRECISION),
+                    );
+                    if ui
+                        .button(RichText::new(X))
+                        .on_hover_text("Remove mass to charge")
+                        .clicked()
+                    {
+                        remove = Some(index);
+                    }
+                });
+            }
+            if let Some(index) = remove {
+                self.list.remove(index);
+            }
+            if ui
+                .button(RichText::new(PLUS))
+                .on_hover_text("Add mass to charge")
+                .clicked()
+            {
+                self.list
+                    .push(self.list.last().copied().unwrap_or_default());
+            }
+        }
+    }
+}
+
+/// Mass to charge tolerance
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Tolerance {
+    pub(crate) value: f32,
+    pub(crate) units: ToleranceUnits,
+}
+
+impl Tolerance {
+    /// Window half width (Da) around the mass to charge
+    pub(crate) fn dalton(self, mass_to_charge: f32) -> f32 {
+        match self.units {
+            ToleranceUnits::Dalton => self.value,
+            ToleranceUnits::Ppm => mass_to_charge * self.value * 1e-6,
+        }
+    }
+
+    /// Value and units editor
+    pub(crate) fn ui(&mut self, ui: &mut Ui, id_source: impl Hash) {
+        let speed = match self.units {
+            ToleranceUnits::Dalton => 0.001,
+            ToleranceUnits::Ppm => 0.1,
+        };
+        ui.add(
+            DragValue::new(&mut self.value)
+                .range(0.0..=f32::MAX)
+                .speed(speed),
+        )
+        .on_hover_text("Half width of the extraction window");
+        ComboBox::from_id_source(id_source)
+            .selected_text(self.units.abbreviation())
+            .show_ui(ui, |ui| {
+                for units in [ToleranceUnits::Dalton, ToleranceUnits::Ppm] {
+                    ui.selectable_value(&mut self.units, units, units.abbreviation())
+                        .on_hover_text(units.description());
+                }
+            })
+            .response
+            .on_hover_text(self.units.description());
+    }
+}
+
+impl Default for Tolerance {
+    /// Nominal mass bin
+    fn default() -> Self {
+        Self {
+            value: 0.5,
+            units: ToleranceUnits::Dalton,
+        }
+    }
+}
+
+impl Hash for Tolerance {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.to_bits().hash(state);
+        self.units.hash(state);
+    }
+}
+
+impl Display for Tolerance {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.value, self.units.abbreviation())
+    }
+}
+
+/// Mass to charge tolerance units
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(crate) enum ToleranceUnits {
+    #[default]
+    Dalton,
+    Ppm,
+}
+
+impl ToleranceUnits {
+    pub(crate) fn abbreviation(&self) -> &'static str {
+        match self {
+            Self::Dalton => "Da",
+            Self::Ppm => "ppm",
+        }
+    }
+
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Self::Dalton => "Absolute tolerance in daltons",
+            Self::Ppm => "Relative tolerance in parts per million",
+        }
+    }
+}
+
+/// Extracted ion chromatogram window centering
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub(crate) enum Centering {
+    #[default]
+    Nominal,
+    List,
+    Centroid,
+}
+
+impl Centering {
+    pub(crate) fn text(&self) -> &'static str {
+        match self {
+            Self::Nominal => "Nominal",
+            Self::List => "List",
+            Self::Centroid => "Centroid",
+        }
+    }
+
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Self::Nominal => "Center windows on nominal (integer) masses",
+            Self::List => "Center windows on a list of masses to charge",
+            Self::Centroid => "Center windows on detected peak centroids",
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default)]
@@ -196,6 +405,16 @@ pub(crate) struct RetentionTime {
 }
 
 impl RetentionTime {
+    /// Value (in milliseconds) in the configured units
+    pub(crate) fn value(self, value: f32) -> f32 {
+        let time = Time::new::<millisecond>(value);
+        match self.units {
+            TimeUnits::Millisecond => time.get::<millisecond>(),
+            TimeUnits::Second => time.get::<second>(),
+            TimeUnits::Minute => time.get::<minute>(),
+        }
+    }
+
     pub(crate) fn format(self, value: f32) -> RetentionTimeFormat {
         RetentionTimeFormat {
             value,
@@ -2,12 +2,28 @@ use super::{
     settings::{Settings, Sort, TimeUnits},
     widgets::{eic::ExtractedIonChromatogram, mass_spectrum::MassSpectrum},
 };
-use crate::app::computers::{TableComputed, TableKey};
-use egui::{Direction, Layout, Ui};
-use egui_ext::TableRowExt;
+use crate::{
+    app::{
+        computers::{computed, is_library, TableComputed, TableKey},
+        data::{name, Compression, Data, Metadata},
+        formats::{
+            csv::{write as to_csv, Delimiter},
+            figure::{Figure, Item, Kind},
+            report::Report,
+            to_xlsx, Format,
+        },
+    },
+    utils::file,
+};
+use anyhow::Result;
+use egui::{Direction, Layout, RichText, Sense, Ui};
+use egui_ext::{color, TableRowExt};
 use egui_extras::{Column, TableBuilder};
+use egui_phosphor::regular::{COPY, FILE_CSV, FILE_HTML, FILE_XLS, FLOPPY_DISK, X};
+use itertools::Itertools;
 use polars::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::{collections::BTreeSet, iter::zip};
 use tracing::error;
 use uom::si::{
     f32::Time,
@@ -20,12 +36,21 @@ const COLUMN_COUNT: usize = 3;
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub(crate) struct TablePane {
     pub(crate) data_frame: DataFrame,
+    #[serde(default)]
+    pub(crate) metadata: Metadata,
     pub(crate) settings: Settings,
+    /// Selected retention times (grouped by retention time)
+    #[serde(skip)]
+    pub(crate) selection: BTreeSet<i32>,
+    /// Last export error
+    #[serde(skip)]
+    pub(crate) error: Option<String>,
 }
 
 impl TablePane {
-    pub(super) fn ui(&self, ui: &mut Ui) {
+    pub(super) fn ui(&mut self, ui: &mut Ui) {
         if let Err(error) = match self.settings.sort {
+            _ if is_library(&self.data_frame) => self.library(ui),
             Sort::RetentionTime if !self.settings.explode => self.grouped_by_retention_time(ui),
             Sort::MassToCharge if !self.settings.explode => self.grouped_by_mass_to_charge(ui),
             _ => self.exploded(ui),
@@ -38,12 +63,12 @@ impl TablePane {
     fn grouped_by_mass_to_charge(&self, ui: &mut Ui) -> PolarsResult<()> {
         let width = ui.spacing().interact_size.x;
         let height = ui.spacing().interact_size.y;
-        let data_frame = ui.memory_mut(|memory| {
+        let data_frame = computed(ui.memory_mut(|memory| {
             memory.caches.cache::<TableComputed>().get(TableKey {
                 data_frame: &self.data_frame,
                 settings: &self.settings,
             })
-        });
+        }))?;
         let total_rows = data_frame.height();
         // let mass_to_charge = .cast(&DataType::UInt32)?;
         let mass_to_charge = data_frame["MassToCharge"].f32()?;
@@ -75,7 +100,11 @@ impl TablePane {
                     row.left_align_col(|ui| {
                         if let Some(value) = mass_to_charge.get(row_index) {
                             let formated = self.settings.mass_to_charge.format(value);
-                            ui.label(formated).on_hover_text(formated.precision(None));
+                            ui.label(formated).on_hover_text(format!(
+                                "{} ± {}",
+                                formated.precision(None),
+                                self.settings.mass_to_charge.tolerance,
+                            ));
                         } else {
                             ui.label("null");
                         }
@@ -93,23 +122,27 @@ impl TablePane {
         Ok(())
     }
 
-    fn grouped_by_retention_time(&self, ui: &mut Ui) -> PolarsResult<()> {
+    fn grouped_by_retention_time(&mut self, ui: &mut Ui) -> PolarsResult<()> {
         let width = ui.spacing().interact_size.x;
         let height = ui.spacing().interact_size.y;
-        let data_frame = ui.memory_mut(|memory| {
+        let data_frame = computed(ui.memory_mut(|memory| {
             memory.caches.cache::<TableComputed>().get(TableKey {
                 data_frame: &self.data_frame,
                 settings: &self.settings,
             })
-        });
+        }))?;
         let total_rows = data_frame.height();
         let retention_time = data_frame["RetentionTime"].i32()?;
+        let base_peak_mass_to_charge = data_frame["BasePeak.MassToCharge"].f32()?;
+        let base_peak_signal = data_frame["BasePeak.Signal"].cast(&DataType::Float64)?;
+        let base_peak_signal = base_peak_signal.f64()?;
         TableBuilder::new(ui)
             .cell_layout(Layout::centered_and_justified(Direction::LeftToRight))
             .column(Column::auto_with_initial_suggestion(width))
-            .columns(Column::auto(), COLUMN_COUNT - 1)
+            .columns(Column::auto(), COLUMN_COUNT)
             .auto_shrink(false)
             .striped(true)
+            .sense(Sense::click())
             .header(height, |mut row| {
                 row.col(|ui| {
                     ui.heading("Index");
@@ -117,6 +150,9 @@ impl TablePane {
                 row.col(|ui| {
                     ui.heading("Retention time");
                 });
+                row.col(|ui| {
+                    ui.heading("Base peak");
+                });
                 row.col(|ui| {
                     ui.heading("MassSpectrum");
                 });
@@ -124,17 +160,482 @@ impl TablePane {
             .body(|body| {
                 body.rows(height, total_rows, |mut row| {
                     let row_index = row.index();
+                    let value = retention_time.get(row_index);
+                    row.set_selected(value.is_some_and(|value| self.selection.contains(&value)));
                     // Index
                     row.col(|ui| {
                         ui.label(row_index.to_string());
                     });
                     // Retention time
                     row.left_align_col(|ui| {
-                        if let Some(value) = retention_time.get(row_index) {
+                        if let Some(value) = value {
                             let formated = self.settings.retention_time.format(value as _);
                             ui.label(formated).on_hover_text(formated.precision(None));
                         }
                     });
+                    // Base peak
+                    row.left_align_col(|ui| {
+                        if let Some(value) = base_peak_mass_to_charge.get(row_index) {
+                            let formated = self.settings.mass_to_charge.format(value);
+                            let signal = base_peak_signal.get(row_index).unwrap_or_default();
+                            ui.label(formated)
+                                .on_hover_text(format!("{}: {signal}", formated.precision(None)));
+                        }
+                    });
+                    // Mass spectrum
+                    row.left_align_col(|ui| {
+                        ui.add(MassSpectrum {
+                            data_frame: &data_frame,
+                            row_index,
+                            settings: &self.settings,
+                        });
+                    });
+                    // Toggle selection
+                    if let Some(value) = value {
+                        if row.response().clicked() && !self.selection.remove(&value) {
+                            self.selection.insert(value);
+                        }
+                    }
+                });
+            });
+        Ok(())
+    }
+
+    /// Selected rows
+    pub(super) fn selection(&mut self, ui: &mut Ui) {
+        if is_library(&self.data_frame) || self.selection.is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label(format!("Selected: {}", self.selection.len()));
+            if ui
+                .button(RichText::new(format!("{FLOPPY_DISK} MGF")))
+                .on_hover_text("Export selected mass spectra as MGF")
+                .clicked()
+            {
+                self.error = self.mgf().err().map(|error| {
+                    error!(%error);
+                    error.to_string()
+                });
+            }
+            if ui
+                .button(RichText::new(format!("{COPY} Copy")))
+                .on_hover_text("Copy selected rows as tab separated values")
+                .clicked()
+            {
+                match self.tsv(ui) {
+                    Ok(text) => {
+                        ui.ctx().copy_text(text);
+                        self.error = None;
+                    }
+                    Err(error) => {
+                        error!(%error);
+                        self.error = Some(error.to_string());
+                    }
+                }
+            }
+            if ui
+                .button(RichText::new(X))
+                .on_hover_text("Clear selection")
+                .clicked()
+            {
+                self.selection.clear();
+            }
+        });
+    }
+
+    /// Displayed table export
+    pub(super) fn export(&mut self, ui: &mut Ui) {
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Export");
+            if ui
+                .button(RichText::new(format!("{FILE_CSV} CSV")))
+                .on_hover_text("Export the displayed table as CSV")
+                .clicked()
+            {
+                self.error = self.csv(ui).err().map(|error| {
+                    error!(%error);
+                    error.to_string()
+                });
+            }
+            if ui
+                .button(RichText::new(format!("{FILE_XLS} XLSX")))
+                .on_hover_text("Export the displayed table as an Excel workbook")
+                .clicked()
+            {
+                self.error = self.xlsx(ui).err().map(|error| {
+                    error!(%error);
+                    error.to_string()
+                });
+            }
+            if ui
+                .button(RichText::new(format!("{FILE_HTML} Report")))
+                .on_hover_text("Export metadata, chromatogram, selected spectra and table as HTML")
+                .clicked()
+            {
+                self.error = self.html(ui).err().map(|error| {
+                    error!(%error);
+                    error.to_string()
+                });
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn csv(&self, ui: &Ui) -> Result<()> {
+        let bytes = to_csv(&mut self.view(ui)?, Delimiter::Comma)?;
+        file::save(&format!("{}.csv", name(&self.metadata)), &bytes)?;
+        Ok(())
+    }
+
+    fn xlsx(&self, ui: &Ui) -> Result<()> {
+        let name = name(&self.metadata);
+        let bytes = to_xlsx(&[(name.clone(), self.view(ui)?)])?;
+        file::save(&format!("{name}.xlsx"), &bytes)?;
+        Ok(())
+    }
+
+    fn html(&self, ui: &Ui) -> Result<()> {
+        let report = self.report(ui)?;
+        file::save(&format!("{}.html", report.name), report.html()?.as_bytes())?;
+        Ok(())
+    }
+
+    /// Grouped by retention time pane (selection applies to it)
+    fn grouped(&self) -> Self {
+        Self {
+            settings: Settings {
+                sort: Sort::RetentionTime,
+                explode: false,
+                base_peak: false,
+                ..self.settings.clone()
+            },
+            ..self.clone()
+        }
+    }
+
+    /// Selected rows of the grouped by retention time view as tab separated
+    /// values.
+    fn tsv(&self, ui: &Ui) -> Result<String> {
+        let pane = self.grouped();
+        let data_frame = computed(ui.memory_mut(|memory| {
+            memory.caches.cache::<TableComputed>().get(TableKey {
+                data_frame: &pane.data_frame,
+                settings: &pane.settings,
+            })
+        }))?;
+        let mask = data_frame["RetentionTime"]
+            .i32()?
+            .into_iter()
+            .map(|value| value.is_some_and(|value| self.selection.contains(&value)))
+            .collect();
+        let bytes = to_csv(&mut pane.view(ui)?.filter(&mask)?, Delimiter::Tab)?;
+        Ok(String::from_utf8(bytes)?)
+    }
+
+    /// Report of the grouped by retention time view (whatever the displayed
+    /// sort is) with the selected mass spectra.
+    pub(crate) fn report(&self, ui: &Ui) -> PolarsResult<Report> {
+        let pane = self.grouped();
+        let settings = &pane.settings;
+        let mut report = Report {
+            name: name(&self.metadata),
+            metadata: self.metadata.clone(),
+            table: pane.view(ui)?,
+            ..Default::default()
+        };
+        if is_library(&self.data_frame) {
+            return Ok(report);
+        }
+        let data_frame = computed(ui.memory_mut(|memory| {
+            memory.caches.cache::<TableComputed>().get(TableKey {
+                data_frame: &pane.data_frame,
+                settings,
+            })
+        }))?;
+        let retention_time_label = format!(
+            "Retention time ({})",
+            settings.retention_time.units.abbreviation(),
+        );
+        let retention_time = data_frame["RetentionTime"].i32()?;
+        let signal = data_frame["Signal.Sum"].cast(&DataType::Float64)?;
+        let points = zip(retention_time, signal.f64()?)
+            .filter_map(|(retention_time, signal)| {
+                Some([
+                    settings.retention_time.value(retention_time? as _) as _,
+                    signal?,
+                ])
+            })
+            .collect();
+        report.tic = Some(Figure {
+            x_label: retention_time_label,
+            y_label: "Signal".to_owned(),
+            legend: false,
+            bounds: None,
+            items: vec![Item {
+                name: "TIC".to_owned(),
+                color: color(0),
+                kind: Kind::Line(points),
+            }],
+        });
+        let mass_spectrum = data_frame["MassSpectrum"].list()?;
+        for (index, (retention_time, mass_spectrum)) in zip(retention_time, mass_spectrum)
+            .filter(|(retention_time, _)| {
+                retention_time.is_some_and(|value| self.selection.contains(&value))
+            })
+            .enumerate()
+        {
+            let (Some(retention_time), Some(mass_spectrum)) = (retention_time, mass_spectrum)
+            else {
+                continue;
+            };
+            let mass_spectrum = mass_spectrum.struct_()?;
+            let mass_to_charge = mass_spectrum.field_by_name("MassToCharge")?;
+            let signal = mass_spectrum
+                .field_by_name("Signal")?
+                .cast(&DataType::Float64)?;
+            let bars = zip(mass_to_charge.f32()?, signal.f64()?)
+                .filter_map(|(mass_to_charge, signal)| {
+                    let mass_to_charge = mass_to_charge?;
+                    let name = settings.mass_to_charge.format(mass_to_charge).to_string();
+                    Some(([mass_to_charge as _, signal?], name))
+                })
+                .collect();
+            let title = format!(
+                "{} {}",
+                settings.retention_time.format(retention_time as _),
+                settings.retention_time.units.abbreviation(),
+            );
+            report.spectra.push((
+                title,
+                Figure {
+                    x_label: "Mass to charge".to_owned(),
+                    y_label: "Signal".to_owned(),
+                    legend: false,
+                    bounds: None,
+                    items: vec![Item {
+                        name: String::new(),
+                        color: color(index),
+                        kind: Kind::Bars { width: 0.5, bars },
+                    }],
+                },
+            ));
+        }
+        Ok(report)
+    }
+
+    fn mgf(&self) -> Result<()> {
+        let mask = self.data_frame["RetentionTime"]
+            .i32()?
+            .into_iter()
+            .map(|value| value.is_some_and(|value| self.selection.contains(&value)))
+            .collect();
+        let data = Data {
+            data_frame: self.data_frame.filter(&mask)?,
+            metadata: self.metadata.clone(),
+        };
+        let bytes = data.to_bytes(Format::Mgf, Compression::None)?;
+        file::save(&format!("{}.mgf", data.name()), &bytes)?;
+        Ok(())
+    }
+
+    /// Displayed table (exported): the computed data frame with the
+    /// configured units and precision, list columns are flattened to `x:y`
+    /// pairs.
+    pub(crate) fn view(&self, ui: &Ui) -> PolarsResult<DataFrame> {
+        let data_frame = computed(ui.memory_mut(|memory| {
+            memory.caches.cache::<TableComputed>().get(TableKey {
+                data_frame: &self.data_frame,
+                settings: &self.settings,
+            })
+        }))?;
+        let settings = &self.settings;
+        let retention_time_name = format!(
+            "Retention time ({})",
+            settings.retention_time.units.abbreviation(),
+        );
+        let retention_time = |name: &str, column: &str| {
+            let values = data_frame[column].cast(&DataType::Float32)?;
+            Ok::<_, PolarsError>(Series::new(
+                name,
+                values
+                    .f32()?
+                    .into_iter()
+                    .map(|value| {
+                        let value = settings.retention_time.value(value?);
+                        Some(round(value, settings.retention_time.precision))
+                    })
+                    .collect::<Vec<_>>(),
+            ))
+        };
+        let mass_to_charge = |name: &str, column: &str| {
+            let values = data_frame[column].cast(&DataType::Float32)?;
+            Ok::<_, PolarsError>(Series::new(
+                name,
+                values
+                    .f32()?
+                    .into_iter()
+                    .map(|value| Some(round(value?, settings.mass_to_charge.precision)))
+                    .collect::<Vec<_>>(),
+            ))
+        };
+        let signal = |name: &str, column: &str| {
+            Ok::<_, PolarsError>(data_frame[column].cast(&DataType::Float64)?.with_name(name))
+        };
+        let columns = match settings.sort {
+            _ if is_library(&self.data_frame) => vec![
+                data_frame.column("Name")?.clone(),
+                data_frame.column("Formula")?.clone(),
+                data_frame
+                    .column("MolecularWeight")?
+                    .clone()
+                    .with_name("Molecular weight"),
+                data_frame.column("Cas")?.clone().with_name("CAS"),
+                data_frame
+                    .column("RetentionIndex")?
+                    .clone()
+                    .with_name("Retention index"),
+                pairs(&data_frame["MassSpectrum"], "Mass spectrum", |value| {
+                    settings.mass_to_charge.format(value).to_string()
+                })?,
+                data_frame["MassSpectrum.Count"].clone().with_name("Count"),
+                mass_to_charge("Mass to charge min", "MassToCharge.Min")?,
+                mass_to_charge("Mass to charge max", "MassToCharge.Max")?,
+                signal("Signal min", "Signal.Min")?,
+                signal("Signal max", "Signal.Max")?,
+                signal("Signal sum", "Signal.Sum")?,
+                mass_to_charge("Base peak mass to charge", "BasePeak.MassToCharge")?,
+                signal("Base peak signal", "BasePeak.Signal")?,
+            ],
+            Sort::RetentionTime if !settings.explode => vec![
+                retention_time(&retention_time_name, "RetentionTime")?,
+                pairs(&data_frame["MassSpectrum"], "Mass spectrum", |value| {
+                    settings.mass_to_charge.format(value).to_string()
+                })?,
+                data_frame["MassSpectrum.Count"].clone().with_name("Count"),
+                mass_to_charge("Mass to charge min", "MassToCharge.Min")?,
+                mass_to_charge("Mass to charge max", "MassToCharge.Max")?,
+                signal("Signal min", "Signal.Min")?,
+                signal("Signal max", "Signal.Max")?,
+                signal("Signal sum", "Signal.Sum")?,
+                mass_to_charge("Base peak mass to charge", "BasePeak.MassToCharge")?,
+                signal("Base peak signal", "BasePeak.Signal")?,
+            ],
+            Sort::MassToCharge if !settings.explode => vec![
+                mass_to_charge("Mass to charge", "MassToCharge")?,
+                pairs(
+                    &data_frame["ExtractedIonChromatogram"],
+                    "Extracted ion chromatogram",
+                    |value| settings.retention_time.format(value).to_string(),
+                )?,
+                data_frame["ExtractedIonChromatogram.Count"]
+                    .clone()
+                    .with_name("Count"),
+                retention_time(&format!("{retention_time_name} min"), "RetentionTime.Min")?,
+                retention_time(&format!("{retention_time_name} max"), "RetentionTime.Max")?,
+                signal("Signal min", "Signal.Min")?,
+                signal("Signal max", "Signal.Max")?,
+                signal("Signal sum", "Signal.Sum")?,
+            ],
+            Sort::RetentionTime => vec![
+                retention_time(&retention_time_name, "RetentionTime")?,
+                mass_to_charge("Mass to charge", "MassToCharge")?,
+                signal("Signal", "Signal")?,
+            ],
+            Sort::MassToCharge => vec![
+                mass_to_charge("Mass to charge", "MassToCharge")?,
+                retention_time(&retention_time_name, "RetentionTime")?,
+                signal("Signal", "Signal")?,
+            ],
+        };
+        DataFrame::new(columns)
+    }
+
+    fn library(&self, ui: &mut Ui) -> PolarsResult<()> {
+        let width = ui.spacing().interact_size.x;
+        let height = ui.spacing().interact_size.y;
+        let data_frame = computed(ui.memory_mut(|memory| {
+            memory.caches.cache::<TableComputed>().get(TableKey {
+                data_frame: &self.data_frame,
+                settings: &self.settings,
+            })
+        }))?;
+        let total_rows = data_frame.height();
+        let name = data_frame.column("Name")?.str()?;
+        let formula = data_frame.column("Formula")?.str()?;
+        let molecular_weight = data_frame.column("MolecularWeight")?.f32()?;
+        let cas = data_frame.column("Cas")?.str()?;
+        let retention_index = data_frame.column("RetentionIndex")?.f32()?;
+        TableBuilder::new(ui)
+            .cell_layout(Layout::centered_and_justified(Direction::LeftToRight))
+            .column(Column::auto_with_initial_suggestion(width))
+            .columns(Column::auto(), 6)
+            .auto_shrink(false)
+            .striped(true)
+            .header(height, |mut row| {
+                row.col(|ui| {
+                    ui.heading("Index");
+                });
+                row.col(|ui| {
+                    ui.heading("Name");
+                });
+                row.col(|ui| {
+                    ui.heading("Formula");
+                });
+                row.col(|ui| {
+                    ui.heading("Molecular weight");
+                });
+                row.col(|ui| {
+                    ui.heading("CAS");
+                });
+                row.col(|ui| {
+                    ui.heading("Retention index");
+                });
+                row.col(|ui| {
+                    ui.heading("Mass spectrum");
+                });
+            })
+            .body(|body| {
+                body.rows(height, total_rows, |mut row| {
+                    let row_index = row.index();
+                    // Index
+                    row.col(|ui| {
+                        ui.label(row_index.to_string());
+                    });
+                    // Name
+                    row.left_align_col(|ui| {
+                        if let Some(value) = name.get(row_index) {
+                            ui.label(value);
+                        }
+                    });
+                    // Formula
+                    row.left_align_col(|ui| {
+                        if let Some(value) = formula.get(row_index) {
+                            ui.label(value);
+                        }
+                    });
+                    // Molecular weight
+                    row.left_align_col(|ui| {
+                        if let Some(value) = molecular_weight.get(row_index) {
+                            ui.label(value.to_string());
+                        }
+                    });
+                    // CAS
+                    row.left_align_col(|ui| {
+                        if let Some(value) = cas.get(row_index) {
+                            ui.label(value);
+                        }
+                    });
+                    // Retention index
+                    row.left_align_col(|ui| {
+                        if let Some(value) = retention_index.get(row_index) {
+                            ui.label(value.to_string());
+                        }
+                    });
                     // Mass spectrum
                     row.left_align_col(|ui| {
                         ui.add(MassSpectrum {
@@ -151,16 +652,17 @@ impl TablePane {
     fn exploded(&self, ui: &mut Ui) -> PolarsResult<()> {
         let width = ui.spacing().interact_size.x;
         let height = ui.spacing().interact_size.y;
-        let data_frame = ui.memory_mut(|memory| {
+        let data_frame = computed(ui.memory_mut(|memory| {
             memory.caches.cache::<TableComputed>().get(TableKey {
                 data_frame: &self.data_frame,
                 settings: &self.settings,
             })
-        });
+        }))?;
         let total_rows = data_frame.height();
         let retention_time = data_frame["RetentionTime"].i32()?;
         let mass_to_charge = data_frame["MassToCharge"].f32()?;
-        let signal = data_frame["Signal"].u16()?;
+        let signal = data_frame["Signal"].cast(&DataType::Float64)?;
+        let signal = signal.f64()?;
         TableBuilder::new(ui)
             .cell_layout(Layout::centered_and_justified(Direction::LeftToRight))
             .column(Column::auto_with_initial_suggestion(width))
@@ -245,3 +747,33 @@ impl TablePane {
         Ok(())
     }
 }
+
+/// Rounds to the precision (decimal places)
+fn round(value: f32, precision: usize) -> f64 {
+    let factor = 10f64.powi(precision as _);
+    (value as f64 * factor).round() / factor
+}
+
+/// List of structs column as space separated `x:y` pairs (`x` is formatted)
+fn pairs(series: &Series, name: &str, format: impl Fn(f32) -> String) -> PolarsResult<Series> {
+    let list = series.list()?;
+    let mut values = Vec::with_capacity(list.len());
+    for row_index in 0..list.len() {
+        let Some(items) = list.get_as_series(row_index) else {
+            values.push(None);
+            continue;
+        };
+        let fields = items.struct_()?.fields_as_series();
+        let [x, y] = &fields[..] else {
+            values.push(None);
+            continue;
+        };
+        let x = x.cast(&DataType::Float32)?;
+        let y = y.cast(&DataType::Float64)?;
+        let text = zip(x.f32()?, y.f64()?)
+            .filter_map(|(x, y)| Some(format!("{}:{}", format(x?), y?)))
+            .join(" ");
+        values.push(Some(text));
+    }
+    Ok(Series::new(name, values))
+}
@@ -0,0 +1,33 @@
+use egui::{RichText, Ui};
+use egui_phosphor::regular::COPY;
+use std::fmt::Write;
+
+/// Copy button, copies the text to the clipboard and closes the menu.
+pub(crate) fn button(ui: &mut Ui, text: &str, hover: &str, copy: impl FnOnce() -> String) {
+    if ui
+        .button(RichText::new(format!("{COPY} {text}")))
+        .on_hover_text(hover)
+        .clicked()
+    {
+        ui.ctx().copy_text(copy());
+        ui.close_menu();
+    }
+}
+
+/// Tab separated values with a header row
+pub(crate) fn tsv(header: [&str; 2], rows: &[[String; 2]]) -> String {
+    let mut text = header.join("\t");
+    text.push('\n');
+    for [x, y] in rows {
+        writeln!(text, "{x}\t{y}").ok();
+    }
+    text
+}
+
+/// Space separated `x:y` pairs
+pub(crate) fn pairs(rows: &[[String; 2]]) -> String {
+    rows.iter()
+        .map(|[x, y]| format!("{x}:{y}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
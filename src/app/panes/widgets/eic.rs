@@ -1,8 +1,11 @@
+use super::copy;
 use crate::app::panes::settings::Settings;
 use egui::{Direction, Layout, Response, RichText, Ui, Widget};
 use egui_extras::{Column, TableBuilder};
 use egui_phosphor::regular::LIST;
 use polars::prelude::*;
+use std::iter::zip;
+use tracing::error;
 
 // https://en.wikipedia.org/wiki/Mass_chromatogram
 
@@ -13,6 +16,45 @@ pub struct ExtractedIonChromatogram<'a> {
     pub(crate) settings: &'a Settings,
 }
 
+impl ExtractedIonChromatogram<'_> {
+    /// Displayed `[retention time, signal]` rows
+    fn rows(&self, retention_time: &Series, signal: &Series) -> PolarsResult<Vec<[String; 2]>> {
+        let signal = signal.cast(&DataType::Float64)?;
+        Ok(zip(retention_time.i32()?, signal.f64()?)
+            .filter_map(|(retention_time, signal)| {
+                Some([
+                    self.settings
+                        .retention_time
+                        .format(retention_time? as _)
+                        .to_string(),
+                    signal?.to_string(),
+                ])
+            })
+            .collect())
+    }
+
+    fn copy(&self, ui: &mut Ui, retention_time: &Series, signal: &Series) {
+        let rows = || {
+            self.rows(retention_time, signal).unwrap_or_else(|error| {
+                error!(%error);
+                Vec::new()
+            })
+        };
+        let header = format!(
+            "Retention time ({})",
+            self.settings.retention_time.units.abbreviation(),
+        );
+        ui.horizontal(|ui| {
+            copy::button(ui, "TSV", "Copy as tab separated values", || {
+                copy::tsv([&header, "Signal"], &rows())
+            });
+            copy::button(ui, "time:intensity", "Copy as time:intensity pairs", || {
+                copy::pairs(&rows())
+            });
+        });
+    }
+}
+
 impl Widget for ExtractedIonChromatogram<'_> {
     fn ui(self, ui: &mut Ui) -> Response {
         let height = ui.spacing().interact_size.y;
@@ -62,6 +104,8 @@ impl Widget for ExtractedIonChromatogram<'_> {
                     .field_by_name("RetentionTime")
                     .unwrap();
                 let signal_series = retention_time_signal.field_by_name("Signal").unwrap();
+                self.copy(ui, &retention_time_series, &signal_series);
+                ui.separator();
                 TableBuilder::new(ui)
                     .cell_layout(Layout::centered_and_justified(Direction::LeftToRight))
                     .column(Column::auto_with_initial_suggestion(width))
@@ -95,7 +139,8 @@ impl Widget for ExtractedIonChromatogram<'_> {
                             });
                             // Signal
                             row.col(|ui| {
-                                let signal = signal_series.u16().unwrap();
+                                let signal = signal_series.cast(&DataType::Float64).unwrap();
+                                let signal = signal.f64().unwrap();
                                 ui.label(signal.get(row_index).unwrap().to_string());
                             });
                         });
@@ -1,8 +1,18 @@
-use crate::app::panes::settings::Settings;
+use super::copy;
+use crate::{
+    app::{
+        formats::{to_jcamp, to_msp, Spectrum},
+        panes::settings::Settings,
+    },
+    utils::file,
+};
+use anyhow::Result;
 use egui::{Direction, Layout, Response, RichText, Ui, Widget};
 use egui_extras::{Column, TableBuilder};
-use egui_phosphor::regular::LIST;
+use egui_phosphor::regular::{FLOPPY_DISK, LIST};
 use polars::prelude::*;
+use std::iter::zip;
+use tracing::error;
 
 /// Mass spectrum widget
 pub struct MassSpectrum<'a> {
@@ -11,6 +21,108 @@ pub struct MassSpectrum<'a> {
     pub(crate) settings: &'a Settings,
 }
 
+impl MassSpectrum<'_> {
+    /// Library spectra have a name instead of a retention time
+    fn name(&self) -> Result<Option<String>> {
+        Ok(match self.data_frame.column("Name") {
+            Ok(name) => name.str()?.get(self.row_index).map(ToOwned::to_owned),
+            Err(_) => None,
+        })
+    }
+
+    fn retention_time(&self) -> Result<i32> {
+        Ok(match self.data_frame.column("RetentionTime") {
+            Ok(retention_time) => retention_time.i32()?.get(self.row_index),
+            Err(_) => None,
+        }
+        .unwrap_or_default())
+    }
+
+    fn title(&self) -> Result<String> {
+        Ok(match self.name()? {
+            Some(name) => name,
+            None => format!(
+                "Retention time {} {}",
+                self.settings
+                    .retention_time
+                    .format(self.retention_time()? as _),
+                self.settings.retention_time.units.abbreviation(),
+            ),
+        })
+    }
+
+    fn spectrum(&self, mass_to_charge: &Series, signal: &Series) -> Result<Spectrum> {
+        let signal = signal.cast(&DataType::Float32)?;
+        let (mass_to_charge, signal) = zip(mass_to_charge.f32()?, signal.f32()?)
+            .filter_map(|(mass_to_charge, signal)| Some((mass_to_charge?, signal?)))
+            .unzip();
+        Ok(Spectrum {
+            retention_time: self.retention_time()? as _,
+            mass_to_charge,
+            signal,
+        })
+    }
+
+    fn jcamp(&self, mass_to_charge: &Series, signal: &Series) -> Result<()> {
+        let file_name = match self.name()? {
+            Some(_) => format!("{}.jdx", self.row_index),
+            None => format!("{}.jdx", self.retention_time()?),
+        };
+        let spectrum = self.spectrum(mass_to_charge, signal)?;
+        file::save(&file_name, to_jcamp(&self.title()?, &spectrum).as_bytes())?;
+        Ok(())
+    }
+
+    fn msp(&self, mass_to_charge: &Series, signal: &Series) -> Result<String> {
+        Ok(to_msp(
+            &self.title()?,
+            &self.spectrum(mass_to_charge, signal)?,
+        ))
+    }
+
+    /// Displayed `[mass to charge, signal]` rows
+    fn rows(&self, mass_to_charge: &Series, signal: &Series) -> Result<Vec<[String; 2]>> {
+        let signal = signal.cast(&DataType::Float64)?;
+        Ok(zip(mass_to_charge.f32()?, signal.f64()?)
+            .filter_map(|(mass_to_charge, signal)| {
+                Some([
+                    self.settings
+                        .mass_to_charge
+                        .format(mass_to_charge?)
+                        .to_string(),
+                    signal?.to_string(),
+                ])
+            })
+            .collect())
+    }
+
+    fn copy(&self, ui: &mut Ui, mass_to_charge: &Series, signal: &Series) {
+        let text = |result: Result<String>| {
+            result.unwrap_or_else(|error| {
+                error!(%error);
+                String::new()
+            })
+        };
+        ui.horizontal(|ui| {
+            copy::button(ui, "TSV", "Copy as tab separated values", || {
+                text(
+                    self.rows(mass_to_charge, signal)
+                        .map(|rows| copy::tsv(["Mass to charge", "Signal"], &rows)),
+                )
+            });
+            copy::button(ui, "MSP", "Copy as MSP peak list (NIST MS Search)", || {
+                text(self.msp(mass_to_charge, signal))
+            });
+            copy::button(ui, "m/z:intensity", "Copy as m/z:intensity pairs", || {
+                text(
+                    self.rows(mass_to_charge, signal)
+                        .map(|rows| copy::pairs(&rows)),
+                )
+            });
+        });
+    }
+}
+
 impl Widget for MassSpectrum<'_> {
     fn ui(self, ui: &mut Ui) -> Response {
         let height = ui.spacing().interact_size.y;
@@ -57,6 +169,29 @@ impl Widget for MassSpectrum<'_> {
                 let mass_to_charge_series =
                     mass_to_charge_signal.field_by_name("MassToCharge").unwrap();
                 let signal_series = mass_to_charge_signal.field_by_name("Signal").unwrap();
+                // Last export error, in egui memory as the widget is rebuilt each frame
+                let id = ui.make_persistent_id("jcamp_error");
+                if ui
+                    .button(RichText::new(format!("{FLOPPY_DISK} JCAMP-DX")))
+                    .on_hover_text("Export mass spectrum as JCAMP-DX")
+                    .clicked()
+                {
+                    match self.jcamp(&mass_to_charge_series, &signal_series) {
+                        Ok(()) => {
+                            ui.data_mut(|data| data.remove::<String>(id));
+                            ui.close_menu();
+                        }
+                        Err(error) => {
+                            error!(%error);
+                            ui.data_mut(|data| data.insert_temp(id, error.to_string()));
+                        }
+                    }
+                }
+                if let Some(error) = ui.data(|data| data.get_temp::<String>(id)) {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                self.copy(ui, &mass_to_charge_series, &signal_series);
+                ui.separator();
                 TableBuilder::new(ui)
                     .cell_layout(Layout::centered_and_justified(Direction::LeftToRight))
                     .column(Column::auto_with_initial_suggestion(width))
@@ -1,2 +1,3 @@
+pub(crate) mod copy;
 pub(crate) mod eic;
 pub(crate) mod mass_spectrum;
@@ -0,0 +1,261 @@
+use super::{
+    peaks::{markers, table, Detection},
+    settings::{Centering, MassToCharge, Settings, Sort, Tolerance},
+};
+use crate::app::{
+    computers::{computed, is_library, PeaksComputed, PeaksKey, TableComputed, TableKey},
+    data::Metadata,
+    MAX_PRECISION,
+};
+use anyhow::{Context, Result};
+use egui::{emath::round_to_decimals, Color32, DragValue, Grid, RichText, TextEdit, Ui};
+use egui_ext::color;
+use egui_phosphor::regular::{PLUS, X};
+use egui_plot::{Legend, Line, Plot, PlotPoints};
+use polars::{datatypes::DataType, error::PolarsResult, frame::DataFrame};
+use serde::{Deserialize, Serialize};
+use std::iter::zip;
+use tracing::error;
+
+/// Extracted ion chromatograms pane (user defined ions overlaid)
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub(crate) struct XicPane {
+    pub(crate) data_frame: DataFrame,
+    #[serde(default)]
+    pub(crate) metadata: Metadata,
+    pub(crate) settings: Settings,
+    #[serde(default)]
+    pub(crate) ions: Vec<Ion>,
+    #[serde(default)]
+    pub(crate) peaks: Detection,
+    /// Typed or pasted masses to charge
+    #[serde(skip)]
+    pub(crate) text: String,
+    /// Last ions parse error
+    #[serde(skip)]
+    pub(crate) error: Option<String>,
+}
+
+impl XicPane {
+    pub(super) fn ui(&self, ui: &mut Ui) {
+        if is_library(&self.data_frame) {
+            ui.label("Spectral libraries have no retention time to plot");
+            return;
+        }
+        if self.ions.is_empty() {
+            ui.label("Add ions in the pane settings");
+            return;
+        }
+        if let Err(error) = self.plot(ui) {
+            error!(%error);
+            ui.label(error.to_string());
+        }
+    }
+
+    /// Ion list
+    pub(super) fn ions(&mut self, ui: &mut Ui) {
+        ui.separator();
+        let mut remove = None;
+        Grid::new("ions").striped(true).show(ui, |ui| {
+            ui.label("Mass to charge");
+            ui.label("Tolerance");
+            ui.label("Color");
+            ui.label("Scale");
+            ui.end_row();
+            for (index, ion) in self.ions.iter_mut().enumerate() {
+                ui.add(
+                    DragValue::new(&mut ion.mass_to_charge)
+                        .range(0.0..=f32::MAX)
+                        .speed(0.01)
+                        .max_decimals(MAX_PRECISION),
+                );
+                ui.horizontal(|ui| ion.tolerance.ui(ui, ("ion_tolerance_units", index)));
+                ui.color_edit_button_srgba(&mut ion.color);
+                ui.add(
+                    DragValue::new(&mut ion.scale)
+                        .range(0.0..=f32::MAX)
+                        .speed(0.1)
+                        .prefix("×"),
+                )
+                .on_hover_text("Signal scale factor");
+                if ui
+                    .button(RichText::new(X))
+                    .on_hover_text("Remove ion")
+                    .clicked()
+                {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(index) = remove {
+            self.ions.remove(index);
+        }
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::multiline(&mut self.text)
+                    .desired_rows(1)
+                    .hint_text("73 147 207 281"),
+            );
+            if ui
+                .button(RichText::new(format!("{PLUS} Add")))
+                .on_hover_text("Add ions, masses to charge separated by spaces, commas or lines")
+                .clicked()
+            {
+                self.error = self.add().err().map(|error| {
+                    error!(%error);
+                    error.to_string()
+                });
+            }
+        });
+        if let Some(error) = &self.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    /// Adds the typed masses to charge with the default tolerance
+    fn add(&mut self) -> Result<()> {
+        let values = self
+            .text
+            .split(|char: char| char.is_whitespace() || char == ',' || char == ';')
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse()
+                    .with_context(|| format!("invalid mass to charge {value:?}"))
+            })
+            .collect::<Result<Vec<f32>>>()?;
+        for mass_to_charge in values {
+            self.ions.push(Ion {
+                mass_to_charge,
+                tolerance: self.settings.mass_to_charge.tolerance,
+                color: color(self.ions.len()),
+                scale: 1.0,
+            });
+        }
+        self.text.clear();
+        Ok(())
+    }
+
+    fn plot(&self, ui: &mut Ui) -> PolarsResult<()> {
+        let retention_time = self.settings.retention_time;
+        let mut lines = Vec::with_capacity(self.ions.len());
+        for ion in &self.ions {
+            // Extraction of the mass to charge branch with the ion window
+            let settings = Settings {
+                explode: false,
+                sort: Sort::MassToCharge,
+                mass_to_charge: MassToCharge {
+                    tolerance: ion.tolerance,
+                    centering: Centering::List,
+                    list: vec![ion.mass_to_charge],
+                    ..self.settings.mass_to_charge.clone()
+                },
+                ..self.settings.clone()
+            };
+            let data_frame = computed(ui.memory_mut(|memory| {
+                memory.caches.cache::<TableComputed>().get(TableKey {
+                    data_frame: &self.data_frame,
+                    settings: &settings,
+                })
+            }))?;
+            let mut points = Vec::new();
+            if let Some(extracted_ion_chromatogram) = data_frame["ExtractedIonChromatogram"]
+                .list()?
+                .get_as_series(0)
+            {
+                let extracted_ion_chromatogram = extracted_ion_chromatogram.struct_()?;
+                let time = extracted_ion_chromatogram.field_by_name("RetentionTime")?;
+                let signal = extracted_ion_chromatogram
+                    .field_by_name("Signal")?
+                    .cast(&DataType::Float64)?;
+                for (time, signal) in zip(time.i32()?, signal.f64()?) {
+                    if let (Some(time), Some(signal)) = (time, signal) {
+                        points.push([
+                            retention_time.value(time as _) as _,
+                            signal * ion.scale as f64,
+                        ]);
+                    }
+                }
+            }
+            let peaks = if self.peaks.enabled {
+                ui.memory_mut(|memory| {
+                    memory.caches.cache::<PeaksComputed>().get(PeaksKey {
+                        points: &points,
+                        settings: &self.peaks,
+                    })
+                })
+            } else {
+                Vec::new()
+            };
+            let name = ion.name(&self.settings.mass_to_charge);
+            lines.push((name, ion.color, points, peaks));
+        }
+        let units = retention_time.units.abbreviation();
+        let precision = retention_time.precision;
+        let mut plot = Plot::new("xic");
+        if self.peaks.enabled {
+            // Leave room for the peak list
+            plot = plot.height(ui.available_height() * 2.0 / 3.0);
+        }
+        plot.legend(Legend::default())
+            .x_axis_label(format!("Retention time ({units})"))
+            .y_axis_label("Signal")
+            .y_axis_formatter(move |y, _| round_to_decimals(y.value, 5).to_string())
+            .label_formatter(move |name, point| {
+                let text = format!(
+                    "Retention time: {:.precision$} {units}\nSignal: {}",
+                    point.x,
+                    round_to_decimals(point.y, 5),
+                );
+                if name.is_empty() {
+                    text
+                } else {
+                    format!("{name}\n{text}")
+                }
+            })
+            .show(ui, |ui| {
+                for (name, color, points, peaks) in &lines {
+                    let line = Line::new(PlotPoints::from(points.clone()))
+                        .name(name)
+                        .color(*color);
+                    ui.line(line);
+                    markers(ui, name, *color, peaks);
+                }
+            });
+        if self.peaks.enabled {
+            ui.separator();
+            let peaks = lines
+                .iter()
+                .flat_map(|(name, _, _, peaks)| peaks.iter().map(|&peak| (name.as_str(), peak)))
+                .collect::<Vec<_>>();
+            table(ui, &peaks, retention_time);
+        }
+        Ok(())
+    }
+}
+
+/// Overlaid ion
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Ion {
+    pub(crate) mass_to_charge: f32,
+    pub(crate) tolerance: Tolerance,
+    pub(crate) color: Color32,
+    /// Signal scale factor
+    pub(crate) scale: f32,
+}
+
+impl Ion {
+    /// Legend name, the scale is shown when it is not one
+    fn name(&self, settings: &MassToCharge) -> String {
+        let mut name = format!(
+            "{} ± {}",
+            settings.format(self.mass_to_charge),
+            self.tolerance,
+        );
+        if self.scale != 1.0 {
+            name.push_str(&format!(" ×{}", self.scale));
+        }
+        name
+    }
+}
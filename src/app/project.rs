@@ -0,0 +1,170 @@
+use super::{
+    data::Data,
+    formats::Format,
+    panes::{behavior::Behavior, Pane},
+};
+use anyhow::{bail, ensure, Context, Result};
+use egui_tiles::{Tile, TileId, Tree};
+use itertools::Itertools;
+use polars::frame::DataFrame;
+use serde::{Deserialize, Serialize};
+use std::{
+    io::{Cursor, Read, Write},
+    mem::take,
+};
+use tracing::info;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+/// Project file extension
+pub(crate) const EXTENSION: &str = "msv";
+/// Project schema version
+const VERSION: u32 = 1;
+/// Manifest entry of the project archive
+const MANIFEST: &str = "project.ron";
+
+/// Project: every loaded dataset with the panes showing it (settings,
+/// metadata) and their layout.
+///
+/// A project file is a zip archive with a RON manifest (schema version, pane
+/// tree, behavior) and a Parquet file per dataset, so that the manifest
+/// stays readable and the datasets stay compact. Parquet is native only, so
+/// projects can't be saved or opened on the web.
+#[derive(Clone, Debug)]
+pub(crate) struct Project {
+    pub(crate) tree: Tree<Pane>,
+    pub(crate) behavior: Behavior,
+}
+
+impl Project {
+    pub(crate) fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut tree = self.tree.clone();
+        // Move the data frames out of the panes, equal ones are stored once
+        let mut datasets = Vec::<DataFrame>::new();
+        let mut panes = Vec::new();
+        let tile_ids = tree.tiles.iter().map(|(tile_id, _)| *tile_id).sorted();
+        for tile_id in tile_ids.collect_vec() {
+            if let Some(Tile::Pane(pane)) = tree.tiles.get_mut(tile_id) {
+                let data_frame = take(pane.data_frame_mut());
+                let index = match datasets
+                    .iter()
+                    .position(|dataset| dataset.equals_missing(&data_frame))
+                {
+                    Some(index) => index,
+                    None => {
+                        datasets.push(data_frame);
+                        datasets.len() - 1
+                    }
+                };
+                panes.push((tile_id, index));
+            }
+        }
+        let manifest = Manifest {
+            version: VERSION,
+            tree,
+            behavior: self.behavior.clone(),
+            panes,
+        };
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.start_file(MANIFEST, options)?;
+        writer.write_all(ron::ser::to_string_pretty(&manifest, Default::default())?.as_bytes())?;
+        // Parquet is compressed already
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        for (index, data_frame) in datasets.into_iter().enumerate() {
+            writer.start_file(dataset(index), options)?;
+            writer.write_all(&Format::Parquet.write(&Data::new(data_frame))?)?;
+        }
+        Ok(writer.finish()?.into_inner())
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+        let mut text = String::new();
+        archive
+            .by_name(MANIFEST)
+            .context("not a project, the manifest is missing")?
+            .read_to_string(&mut text)?;
+        let Version { version } = ron::from_str(&text)?;
+        info!(version, "project");
+        let Manifest {
+            mut tree,
+            behavior,
+            panes,
+            ..
+        } = migrate(version, &text)?;
+        let mut datasets = Vec::<DataFrame>::new();
+        for (tile_id, index) in panes {
+            while datasets.len() <= index {
+                let mut bytes = Vec::new();
+                archive
+                    .by_name(&dataset(datasets.len()))?
+                    .read_to_end(&mut bytes)?;
+                datasets.push(Format::Parquet.read(bytes)?.data_frame);
+            }
+            let Some(Tile::Pane(pane)) = tree.tiles.get_mut(tile_id) else {
+                bail!("project pane {tile_id:?} not found");
+            };
+            *pane.data_frame_mut() = datasets[index].clone();
+        }
+        Ok(Self { tree, behavior })
+    }
+}
+
+/// Project manifest (current schema version)
+#[derive(Deserialize, Serialize)]
+struct Manifest {
+    version: u32,
+    /// Panes without their data frames
+    tree: Tree<Pane>,
+    behavior: Behavior,
+    /// Dataset index of each pane
+    panes: Vec<(TileId, usize)>,
+}
+
+/// Schema version, read before the manifest itself
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+/// Reads a manifest of the version, migrating older schemas to the current
+/// one.
+///
+/// Fields added with a default need no migration. A breaking schema change
+/// bumps [`VERSION`] and adds a step here that converts the previous
+/// manifest.
+fn migrate(version: u32, text: &str) -> Result<Manifest> {
+    ensure!(
+        version <= VERSION,
+        "project version {version} is newer than the supported {VERSION}",
+    );
+    match version {
+        VERSION => Ok(ron::from_str(text)?),
+        _ => bail!("unsupported project version {version}"),
+    }
+}
+
+fn dataset(index: usize) -> String {
+    format!("datasets/{index}.parquet")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn project(version: u32) -> Result<Vec<u8>> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.start_file(MANIFEST, SimpleFileOptions::default())?;
+        writer.write_all(format!("(version: {version})").as_bytes())?;
+        Ok(writer.finish()?.into_inner())
+    }
+
+    #[test]
+    fn unsupported_version() -> Result<()> {
+        let error = Project::from_bytes(&project(0)?).unwrap_err();
+        assert_eq!(error.to_string(), "unsupported project version 0");
+        let error = Project::from_bytes(&project(VERSION + 1)?).unwrap_err();
+        assert!(error.to_string().contains("is newer than the supported"));
+        Ok(())
+    }
+}
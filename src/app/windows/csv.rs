@@ -0,0 +1,197 @@
+use crate::app::{
+    data::Data,
+    formats::csv::{preview, read, Columns, Decimal, Delimiter, Options},
+    panes::settings::TimeUnits,
+};
+use anyhow::Result;
+use egui::{Button, ComboBox, Context, Grid, Id, RichText, ScrollArea, Ui, Window};
+use polars::prelude::*;
+
+const PREVIEW: usize = 10;
+
+/// CSV/TSV import window (long format: a row per peak)
+pub(crate) struct CsvWindow {
+    name: String,
+    bytes: Vec<u8>,
+    options: Options,
+    columns: Columns,
+    preview: Result<DataFrame>,
+    error: Option<String>,
+}
+
+impl CsvWindow {
+    pub(crate) fn new(name: String, bytes: Vec<u8>, delimiter: Delimiter) -> Self {
+        let options = Options {
+            delimiter,
+            header: true,
+            ..Default::default()
+        };
+        let mut window = Self {
+            name,
+            bytes,
+            options,
+            columns: Default::default(),
+            preview: Ok(Default::default()),
+            error: None,
+        };
+        window.preview();
+        window
+    }
+
+    /// Shows the window, returns the imported data and whether the window
+    /// stays open.
+    pub(crate) fn show(&mut self, ctx: &Context) -> (Option<Data>, bool) {
+        let mut open = true;
+        let mut data = None;
+        Window::new(format!("Import {}", self.name))
+            .id(Id::new("csv_window"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                let options = self.options;
+                self.options(ui);
+                if self.options != options {
+                    self.preview();
+                }
+                ui.separator();
+                self.columns(ui);
+                ui.separator();
+                self.table(ui);
+                ui.separator();
+                if let Some(error) = &self.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                if ui
+                    .add_enabled(self.preview.is_ok(), Button::new("Import"))
+                    .clicked()
+                {
+                    match read(&self.bytes, self.options, &self.columns) {
+                        Ok(data_frame) => {
+                            let mut imported = Data::new(data_frame);
+                            imported
+                                .metadata
+                                .insert("FileName".to_owned(), self.name.clone());
+                            data = Some(imported);
+                        }
+                        Err(error) => self.error = Some(error.to_string()),
+                    }
+                }
+            });
+        let open = open && data.is_none();
+        (data, open)
+    }
+
+    fn options(&mut self, ui: &mut Ui) {
+        Grid::new("csv_options").show(ui, |ui| {
+            ui.label("Delimiter");
+            ComboBox::from_id_source("csv_delimiter")
+                .selected_text(self.options.delimiter.text())
+                .show_ui(ui, |ui| {
+                    for delimiter in [Delimiter::Comma, Delimiter::Semicolon, Delimiter::Tab] {
+                        ui.selectable_value(
+                            &mut self.options.delimiter,
+                            delimiter,
+                            delimiter.text(),
+                        );
+                    }
+                });
+            ui.end_row();
+            ui.label("Decimal separator");
+            ComboBox::from_id_source("csv_decimal")
+                .selected_text(self.options.decimal.text())
+                .show_ui(ui, |ui| {
+                    for decimal in [Decimal::Point, Decimal::Comma] {
+                        ui.selectable_value(&mut self.options.decimal, decimal, decimal.text());
+                    }
+                });
+            ui.end_row();
+            ui.label("Header");
+            ui.checkbox(&mut self.options.header, "")
+                .on_hover_text("First row is the header");
+            ui.end_row();
+        });
+    }
+
+    fn columns(&mut self, ui: &mut Ui) {
+        let Ok(data_frame) = &self.preview else {
+            return;
+        };
+        let names = data_frame.get_column_names();
+        Grid::new("csv_columns").show(ui, |ui| {
+            for (text, column) in [
+                ("Retention time", &mut self.columns.retention_time),
+                ("Mass to charge", &mut self.columns.mass_to_charge),
+                ("Signal", &mut self.columns.signal),
+            ] {
+                ui.label(text);
+                ComboBox::from_id_source(text)
+                    .selected_text(&*column)
+                    .show_ui(ui, |ui| {
+                        for &name in &names {
+                            ui.selectable_value(column, name.to_owned(), name);
+                        }
+                    });
+                ui.end_row();
+            }
+            ui.label("Retention time units");
+            ComboBox::from_id_source("csv_retention_time_units")
+                .selected_text(self.columns.units.singular())
+                .show_ui(ui, |ui| {
+                    for units in [TimeUnits::Millisecond, TimeUnits::Second, TimeUnits::Minute] {
+                        ui.selectable_value(&mut self.columns.units, units, units.singular())
+                            .on_hover_text(units.abbreviation());
+                    }
+                });
+            ui.end_row();
+        });
+    }
+
+    fn table(&self, ui: &mut Ui) {
+        let data_frame = match &self.preview {
+            Ok(data_frame) => data_frame,
+            Err(error) => {
+                ui.colored_label(ui.visuals().error_fg_color, error.to_string());
+                return;
+            }
+        };
+        ScrollArea::both().max_height(256.0).show(ui, |ui| {
+            Grid::new("csv_preview").striped(true).show(ui, |ui| {
+                for name in data_frame.get_column_names() {
+                    ui.label(RichText::new(name).strong());
+                }
+                ui.end_row();
+                for index in 0..data_frame.height() {
+                    for series in data_frame.get_columns() {
+                        match series.get(index) {
+                            Ok(AnyValue::Null) | Err(_) => ui.label(""),
+                            Ok(value) => ui.label(value.to_string()),
+                        };
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    /// Rereads the preview rows and guesses the columns
+    fn preview(&mut self) {
+        self.error = None;
+        self.preview = preview(&self.bytes, self.options, PREVIEW);
+        let Ok(data_frame) = &self.preview else {
+            return;
+        };
+        let names = data_frame.get_column_names();
+        let guess = |patterns: &[&str], index: usize| {
+            names
+                .iter()
+                .find(|name| {
+                    let name = name.to_lowercase();
+                    patterns.iter().any(|pattern| name.contains(pattern))
+                })
+                .or(names.get(index))
+                .map_or_else(String::new, |name| name.to_string())
+        };
+        self.columns.retention_time = guess(&["time", "rt", "scan"], 0);
+        self.columns.mass_to_charge = guess(&["m/z", "mz", "mass"], 1);
+        self.columns.signal = guess(&["signal", "intensity", "abundance"], 2);
+    }
+}
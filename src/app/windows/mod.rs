@@ -0,0 +1,4 @@
+pub(crate) use self::{csv::CsvWindow, save::SaveWindow};
+
+mod csv;
+mod save;
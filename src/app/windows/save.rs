@@ -0,0 +1,154 @@
+use crate::{
+    app::{
+        computers::computed,
+        data::{Compression, Data},
+        formats::{compress, to_mzml, Bits, Format, MzMlOptions, Scope},
+    },
+    utils::file,
+};
+use anyhow::Result;
+use egui::{ComboBox, Context, Grid, Id, TextEdit, Ui, Window};
+use polars::frame::DataFrame;
+
+/// Save as window
+pub(crate) struct SaveWindow {
+    data: Data,
+    /// Computed (filtered, normalized) data frame
+    computed: DataFrame,
+    name: String,
+    format: Format,
+    compression: Compression,
+    mzml: MzMlOptions,
+    processed: bool,
+    error: Option<String>,
+}
+
+impl SaveWindow {
+    pub(crate) fn new(data: Data, computed: DataFrame) -> Self {
+        let name = data.name();
+        Self {
+            data,
+            computed,
+            name,
+            format: if cfg!(target_arch = "wasm32") {
+                Format::Bin
+            } else {
+                Format::Parquet
+            },
+            compression: Compression::None,
+            mzml: Default::default(),
+            processed: false,
+            error: None,
+        }
+    }
+
+    /// Shows the window, returns whether the window stays open.
+    pub(crate) fn show(&mut self, ctx: &Context) -> bool {
+        let mut open = true;
+        let mut saved = false;
+        Window::new(format!("Save {}", self.name))
+            .id(Id::new("save_window"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                Grid::new("save_options").show(ui, |ui| {
+                    ui.label("Name");
+                    ui.add(TextEdit::singleline(&mut self.name));
+                    ui.end_row();
+                    ui.label("Format");
+                    ComboBox::from_id_source("save_format")
+                        .selected_text(self.format.name())
+                        .show_ui(ui, |ui| {
+                            for format in Format::ALL.into_iter().filter(|format| {
+                                let capabilities = format.capabilities();
+                                capabilities.write && capabilities.scope == Scope::Run
+                            }) {
+                                ui.selectable_value(&mut self.format, format, format.name());
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Compression");
+                    ui.horizontal(|ui| {
+                        for compression in [
+                            Compression::None,
+                            Compression::Gzip,
+                            #[cfg(not(target_arch = "wasm32"))]
+                            Compression::Zstd,
+                        ] {
+                            ui.selectable_value(
+                                &mut self.compression,
+                                compression,
+                                compression.text(),
+                            );
+                        }
+                    });
+                    ui.end_row();
+                    ui.label("Processed");
+                    ui.checkbox(&mut self.processed, "")
+                        .on_hover_text("Save the filtered and normalized table");
+                    ui.end_row();
+                    if self.format == Format::MzMl {
+                        self.mzml(ui);
+                    }
+                });
+                ui.separator();
+                ui.label(self.file_name());
+                if let Some(error) = &self.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                if ui.button("Save").clicked() {
+                    match self.save() {
+                        Ok(done) => saved = done,
+                        Err(error) => self.error = Some(error.to_string()),
+                    }
+                }
+            });
+        open && !saved
+    }
+
+    fn mzml(&mut self, ui: &mut Ui) {
+        ui.label("m/z array");
+        bits(ui, &mut self.mzml.mass_to_charge);
+        ui.end_row();
+        ui.label("Intensity array");
+        bits(ui, &mut self.mzml.intensity);
+        ui.end_row();
+        ui.label("Zlib");
+        ui.checkbox(&mut self.mzml.zlib, "")
+            .on_hover_text("Compress binary data arrays");
+        ui.end_row();
+        ui.label("Index");
+        ui.checkbox(&mut self.mzml.index, "")
+            .on_hover_text("indexedmzML with spectrum offsets");
+        ui.end_row();
+    }
+
+    fn file_name(&self) -> String {
+        format!(
+            "{}.{}{}",
+            self.name.trim(),
+            self.format.extensions()[0],
+            self.compression.extension(),
+        )
+    }
+
+    /// Returns `false` if the dialog was canceled.
+    fn save(&self) -> Result<bool> {
+        let mut data = self.data.clone();
+        if self.processed {
+            data.data_frame = computed(self.computed.clone())?;
+        }
+        let bytes = match self.format {
+            Format::MzMl => to_mzml(&data, self.mzml)?,
+            format => format.write(&data)?,
+        };
+        file::save(&self.file_name(), &compress(bytes, self.compression)?)
+    }
+}
+
+fn bits(ui: &mut Ui, bits: &mut Bits) {
+    ui.horizontal(|ui| {
+        for value in [Bits::X32, Bits::X64] {
+            ui.selectable_value(bits, value, value.text());
+        }
+    });
+}
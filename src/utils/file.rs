@@ -0,0 +1,78 @@
+use anyhow::Result;
+
+/// Files picked in the browser (name and content), filled asynchronously
+#[cfg(target_arch = "wasm32")]
+pub(crate) type Picked = std::sync::Arc<std::sync::Mutex<Vec<(String, Vec<u8>)>>>;
+
+/// Picks files with a native file dialog.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn pick(extensions: &[&str]) -> Vec<std::path::PathBuf> {
+    rfd::FileDialog::new()
+        .add_filter("Mass spectra", extensions)
+        .add_filter("All files", &["*"])
+        .pick_files()
+        .unwrap_or_default()
+}
+
+/// Picks files with the browser file picker, their content is pushed to
+/// `picked` when read.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn pick(ctx: &egui::Context, extensions: &[&str], picked: Picked) {
+    let dialog = rfd::AsyncFileDialog::new().add_filter("Mass spectra", extensions);
+    let ctx = ctx.clone();
+    wasm_bindgen_futures::spawn_local(async move {
+        for file in dialog.pick_files().await.unwrap_or_default() {
+            let bytes = file.read().await;
+            if let Ok(mut picked) = picked.lock() {
+                picked.push((file.file_name(), bytes));
+            }
+        }
+        ctx.request_repaint();
+    });
+}
+
+/// Saves bytes with a native file dialog, returns `false` if canceled.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn save(file_name: &str, bytes: &[u8]) -> Result<bool> {
+    use rfd::FileDialog;
+    use std::{fs::write, path::Path};
+
+    let mut dialog = FileDialog::new().set_file_name(file_name);
+    if let Some(extension) = Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        dialog = dialog.add_filter(extension, &[extension]);
+    }
+    let Some(path) = dialog.save_file() else {
+        return Ok(false);
+    };
+    write(path, bytes)?;
+    Ok(true)
+}
+
+/// Saves bytes as a browser download.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn save(file_name: &str, bytes: &[u8]) -> Result<bool> {
+    use anyhow::{anyhow, Context};
+    use js_sys::{Array, Uint8Array};
+    use wasm_bindgen::{JsCast, JsValue};
+    use web_sys::{Blob, HtmlAnchorElement, Url};
+
+    let error = |error: JsValue| anyhow!("{error:?}");
+    let blob =
+        Blob::new_with_u8_array_sequence(&Array::of1(&Uint8Array::from(bytes))).map_err(error)?;
+    let url = Url::create_object_url_with_blob(&blob).map_err(error)?;
+    let anchor = web_sys::window()
+        .and_then(|window| window.document())
+        .context("document not found")?
+        .create_element("a")
+        .map_err(error)?
+        .dyn_into::<HtmlAnchorElement>()
+        .map_err(|element| anyhow!("{element:?} is not an anchor"))?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    Url::revoke_object_url(&url).map_err(error)?;
+    Ok(true)
+}
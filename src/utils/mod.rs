@@ -4,4 +4,5 @@ pub(crate) use self::{
 };
 
 mod egui_tiles;
+pub(crate) mod file;
 mod polars;